@@ -0,0 +1,203 @@
+use std::collections::HashMap;
+
+use serde_json::Value;
+
+use crate::models::CommitAttribution;
+
+/// Lines added and removed in one file of a commit.
+#[derive(Debug, Default, PartialEq)]
+pub struct FileDiff {
+    pub path: String,
+    pub added: Vec<String>,
+    pub removed: Vec<String>,
+}
+
+/// Lines the agent wrote and replaced in one file, as multisets of trimmed
+/// line text. Each agent line can account for one committed line.
+#[derive(Debug, Default)]
+pub struct AgentLines {
+    pub written: HashMap<String, usize>,
+    pub replaced: HashMap<String, usize>,
+}
+
+/// Parse `git show --unified=0` output into per-file added and removed lines.
+pub fn parse_diff(diff: &str) -> Vec<FileDiff> {
+    let mut files: Vec<FileDiff> = Vec::new();
+    let mut in_hunk = false;
+    for line in diff.lines() {
+        if line.starts_with("diff --git ") {
+            files.push(FileDiff::default());
+            in_hunk = false;
+        } else if line.starts_with("@@") {
+            in_hunk = true;
+        } else if !in_hunk {
+            // A deleted file has "+++ /dev/null"; take its path from "--- a/"
+            if let Some(path) = line.strip_prefix("--- a/").or_else(|| line.strip_prefix("+++ b/")) {
+                if let Some(file) = files.last_mut() {
+                    file.path = path.to_string();
+                }
+            }
+        } else if let Some(file) = files.last_mut() {
+            if let Some(added) = line.strip_prefix('+') {
+                file.added.push(added.to_string());
+            } else if let Some(removed) = line.strip_prefix('-') {
+                file.removed.push(removed.to_string());
+            }
+        }
+    }
+    files
+}
+
+/// Collect the lines an agent wrote (Write `content`, Edit/MultiEdit
+/// `new_string`) and replaced (`old_string`) from tool inputs, keyed by
+/// absolute file path.
+pub fn agent_lines(tool_inputs: &[(String, String)]) -> HashMap<String, AgentLines> {
+    let mut by_file: HashMap<String, AgentLines> = HashMap::new();
+    for (tool_name, input) in tool_inputs {
+        let Ok(input) = serde_json::from_str::<Value>(input) else {
+            continue;
+        };
+        let Some(path) = input.get("file_path").and_then(|p| p.as_str()) else {
+            continue;
+        };
+        let entry = by_file.entry(path.to_string()).or_default();
+        let field = |v: &Value, k: &str| v.get(k).and_then(|s| s.as_str()).unwrap_or_default().to_string();
+        let edits: Vec<(String, String)> = match tool_name.as_str() {
+            "Write" => vec![(String::new(), field(&input, "content"))],
+            "Edit" => vec![(field(&input, "old_string"), field(&input, "new_string"))],
+            "MultiEdit" => input
+                .get("edits")
+                .and_then(|e| e.as_array())
+                .map(|edits| edits.iter().map(|e| (field(e, "old_string"), field(e, "new_string"))).collect())
+                .unwrap_or_default(),
+            _ => Vec::new(),
+        };
+        for (old, new) in edits {
+            count_lines(&mut entry.replaced, &old);
+            count_lines(&mut entry.written, &new);
+        }
+    }
+    by_file
+}
+
+fn count_lines(counts: &mut HashMap<String, usize>, text: &str) {
+    for line in text.lines().map(str::trim).filter(|l| !l.is_empty()) {
+        *counts.entry(line.to_string()).or_default() += 1;
+    }
+}
+
+/// Classify a commit's changed lines. An added line is agent-authored if it
+/// matches a line the agent wrote to that file; a removed line is the agent's
+/// if it matches a line the agent replaced. Blank lines are not counted.
+/// `root` is the work tree the diff paths are relative to.
+pub fn attribute(root: &str, diffs: &[FileDiff], agent: &HashMap<String, AgentLines>) -> CommitAttribution {
+    let mut result = CommitAttribution::default();
+    for diff in diffs {
+        let mut lines = agent
+            .get(&format!("{}/{}", root.trim_end_matches('/'), diff.path))
+            .map(|a| (a.written.clone(), a.replaced.clone()))
+            .unwrap_or_default();
+        for line in diff.added.iter().map(|l| l.trim()).filter(|l| !l.is_empty()) {
+            if take(&mut lines.0, line) {
+                result.agent_lines += 1;
+            } else {
+                result.human_added += 1;
+            }
+        }
+        for line in diff.removed.iter().map(|l| l.trim()).filter(|l| !l.is_empty()) {
+            if !take(&mut lines.1, line) {
+                result.human_removed += 1;
+            }
+        }
+    }
+    let added = result.agent_lines + result.human_added;
+    if added > 0 {
+        result.agent_percentage = result.agent_lines as f64 * 100.0 / added as f64;
+    }
+    result
+}
+
+fn take(counts: &mut HashMap<String, usize>, line: &str) -> bool {
+    match counts.get_mut(line) {
+        Some(n) if *n > 0 => {
+            *n -= 1;
+            true
+        }
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DIFF: &str = "\
+diff --git a/src/lib.rs b/src/lib.rs
+index 1111111..2222222 100644
+--- a/src/lib.rs
++++ b/src/lib.rs
+@@ -1,2 +1,3 @@
+-fn old() {}
+--- not a header
++fn new() {}
++
++// by hand
+diff --git a/gone.txt b/gone.txt
+deleted file mode 100644
+--- a/gone.txt
++++ /dev/null
+@@ -1 +0,0 @@
+-bye
+";
+
+    #[test]
+    fn parse_diff_files_and_lines() {
+        let files = parse_diff(DIFF);
+        assert_eq!(files.len(), 2);
+        assert_eq!(files[0].path, "src/lib.rs");
+        assert_eq!(files[0].added, vec!["fn new() {}", "", "// by hand"]);
+        assert_eq!(files[0].removed, vec!["fn old() {}", "-- not a header"]);
+        assert_eq!(files[1].path, "gone.txt");
+        assert_eq!(files[1].removed, vec!["bye"]);
+    }
+
+    #[test]
+    fn attribute_agent_and_human_lines() {
+        let inputs = vec![(
+            "Edit".to_string(),
+            r#"{"file_path":"/repo/src/lib.rs","old_string":"fn old() {}","new_string":"  fn new() {}\n"}"#.to_string(),
+        )];
+        let result = attribute("/repo", &parse_diff(DIFF), &agent_lines(&inputs));
+        assert_eq!(result.agent_lines, 1);
+        assert_eq!(result.human_added, 1);
+        // "-- not a header" and gone.txt's "bye"
+        assert_eq!(result.human_removed, 2);
+        assert!((result.agent_percentage - 50.0).abs() < 0.01);
+    }
+
+    #[test]
+    fn agent_lines_match_once_each() {
+        let inputs = vec![
+            ("Write".to_string(), r#"{"file_path":"/r/a","content":"}\n"}"#.to_string()),
+            (
+                "MultiEdit".to_string(),
+                r#"{"file_path":"/r/a","edits":[{"old_string":"x","new_string":"y"}]}"#.to_string(),
+            ),
+        ];
+        let diffs = vec![FileDiff {
+            path: "a".into(),
+            added: vec!["}".into(), "}".into(), "y".into()],
+            removed: vec![],
+        }];
+        let result = attribute("/r", &diffs, &agent_lines(&inputs));
+        assert_eq!((result.agent_lines, result.human_added), (2, 1));
+    }
+
+    #[test]
+    fn attribute_no_agent_edits() {
+        let result = attribute("/repo", &parse_diff(DIFF), &HashMap::new());
+        assert_eq!(result.agent_lines, 0);
+        assert_eq!(result.human_added, 2);
+        assert_eq!(result.agent_percentage, 0.0);
+    }
+}
@@ -0,0 +1,476 @@
+use crate::models::BashCommand;
+
+/// Programs whose first argument names what they do, so `cargo test` and
+/// `cargo build` are counted apart.
+const SUBCOMMAND_PROGRAMS: &[&str] = &[
+    "apt", "apt-get", "brew", "bun", "cargo", "deno", "docker", "dotnet", "gh", "git", "go", "helm", "kubectl",
+    "make", "npm", "pip", "pip3", "pnpm", "poetry", "rustup", "terraform", "uv", "yarn",
+];
+
+/// Package-manager runners whose `run <script>` names the script too.
+const SCRIPT_RUNNERS: &[&str] = &["bun", "npm", "pnpm", "yarn"];
+
+/// Words that run the command after them: `sudo`, `time`, `env FOO=1` and so on.
+const WRAPPERS: &[&str] = &["command", "env", "exec", "nice", "nohup", "sudo", "time", "timeout"];
+
+/// Shell keywords that prefix a command (`if cargo test; then ...`).
+const KEYWORD_PREFIXES: &[&str] = &["!", "do", "elif", "else", "if", "then", "until", "while"];
+
+/// Shell keywords that never start a command worth counting.
+const KEYWORD_SEGMENTS: &[&str] = &["case", "done", "esac", "fi", "for", "function", "select", "{", "}"];
+
+/// Split a Bash tool command into the simple commands it runs, in order.
+/// Pipelines, `&&`/`||` chains, `;`, newlines and subshells all separate
+/// commands. Quotes, escapes, `$(...)` and backticks keep their contents in
+/// one word; redirections and here-document bodies are dropped. Leading
+/// variable assignments and wrappers such as `sudo` and `env` are skipped.
+pub fn parse(command: &str) -> Vec<BashCommand> {
+    split_commands(command)
+        .into_iter()
+        .filter_map(|words| simple_command(&words))
+        .enumerate()
+        .map(|(i, mut c)| {
+            c.position = i as i64;
+            c
+        })
+        .collect()
+}
+
+/// Words of the simple commands read so far.
+#[derive(Default)]
+struct Words {
+    commands: Vec<Vec<String>>,
+    words: Vec<String>,
+    word: String,
+    /// Whether the current word has started; an empty quoted string is a word.
+    in_word: bool,
+    /// The next word is a redirection target, not an argument.
+    skip_next_word: bool,
+}
+
+impl Words {
+    fn push(&mut self, c: char) {
+        self.word.push(c);
+        self.in_word = true;
+    }
+
+    fn extend(&mut self, chars: &[char]) {
+        self.word.extend(chars);
+        self.in_word = true;
+    }
+
+    fn end_word(&mut self) {
+        if !self.in_word {
+            return;
+        }
+        let word = std::mem::take(&mut self.word);
+        if !std::mem::take(&mut self.skip_next_word) {
+            self.words.push(word);
+        }
+        self.in_word = false;
+    }
+
+    fn end_command(&mut self) {
+        self.end_word();
+        if !self.words.is_empty() {
+            self.commands.push(std::mem::take(&mut self.words));
+        }
+    }
+}
+
+/// Tokenize `command` into the words of each simple command.
+fn split_commands(command: &str) -> Vec<Vec<String>> {
+    let chars: Vec<char> = command.chars().collect();
+    let mut w = Words::default();
+    let mut heredocs: Vec<String> = Vec::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        match c {
+            '\\' => {
+                if let Some(&next) = chars.get(i + 1).filter(|&&n| n != '\n') {
+                    w.push(next);
+                }
+                i += 2;
+                continue;
+            }
+            '\'' => {
+                let end = find(&chars, i + 1, '\'');
+                w.extend(&chars[i + 1..end]);
+                i = end + 1;
+                continue;
+            }
+            '"' => {
+                i += 1;
+                while i < chars.len() && chars[i] != '"' {
+                    if chars[i] == '\\' && i + 1 < chars.len() {
+                        i += 1;
+                    } else if chars[i] == '$' && chars.get(i + 1) == Some(&'(') {
+                        let end = matching_paren(&chars, i + 1);
+                        w.extend(&chars[i..end]);
+                        i = end;
+                        continue;
+                    }
+                    w.push(chars[i]);
+                    i += 1;
+                }
+                w.in_word = true;
+                i += 1;
+                continue;
+            }
+            '$' if chars.get(i + 1) == Some(&'(') => {
+                let end = matching_paren(&chars, i + 1);
+                w.extend(&chars[i..end]);
+                i = end;
+                continue;
+            }
+            '`' => {
+                let end = find(&chars, i + 1, '`');
+                w.extend(&chars[i..(end + 1).min(chars.len())]);
+                i = end + 1;
+                continue;
+            }
+            '#' if !w.in_word => {
+                i = find(&chars, i, '\n');
+                continue;
+            }
+            ' ' | '\t' => w.end_word(),
+            '\n' => {
+                w.end_command();
+                for delimiter in std::mem::take(&mut heredocs) {
+                    i = skip_heredoc(&chars, i + 1, &delimiter);
+                }
+            }
+            '<' | '>' => {
+                // A file descriptor number before the operator belongs to it
+                if w.in_word && w.word.chars().all(|d| d.is_ascii_digit()) {
+                    w.word.clear();
+                    w.in_word = false;
+                }
+                w.end_word();
+                let heredoc = c == '<' && chars.get(i + 1) == Some(&'<') && chars.get(i + 2) != Some(&'<');
+                while matches!(chars.get(i + 1), Some('<' | '>' | '&' | '|' | '-')) {
+                    i += 1;
+                }
+                if heredoc {
+                    let (delimiter, end) = heredoc_delimiter(&chars, i + 1);
+                    heredocs.push(delimiter);
+                    i = end;
+                    continue;
+                }
+                // `2>&1` and `>&2` name a descriptor, not a file
+                let target_is_fd = chars[i] == '&' && chars.get(i + 1).is_some_and(|d| d.is_ascii_digit());
+                if target_is_fd {
+                    i += 2;
+                    continue;
+                }
+                w.skip_next_word = true;
+            }
+            '&' if chars.get(i + 1) == Some(&'>') => {
+                w.end_word();
+                i += 2;
+                if chars.get(i) == Some(&'>') {
+                    i += 1;
+                }
+                w.skip_next_word = true;
+                continue;
+            }
+            '|' | '&' | ';' | '(' | ')' => w.end_command(),
+            _ => w.push(c),
+        }
+        i += 1;
+    }
+    w.end_command();
+    w.commands
+}
+
+/// Index of the next `target` at or after `from`, or the end of input.
+fn find(chars: &[char], from: usize, target: char) -> usize {
+    (from..chars.len()).find(|&j| chars[j] == target).unwrap_or(chars.len())
+}
+
+/// Index just past the `)` matching the `(` at `open`, or the end of input.
+fn matching_paren(chars: &[char], open: usize) -> usize {
+    let mut depth = 0;
+    for (j, &c) in chars.iter().enumerate().skip(open) {
+        match c {
+            '(' => depth += 1,
+            ')' => {
+                depth -= 1;
+                if depth == 0 {
+                    return j + 1;
+                }
+            }
+            _ => {}
+        }
+    }
+    chars.len()
+}
+
+/// Read a here-document delimiter starting at `from`, unquoting it. Returns
+/// the delimiter and the index after it.
+fn heredoc_delimiter(chars: &[char], from: usize) -> (String, usize) {
+    let mut i = from;
+    while chars.get(i) == Some(&' ') {
+        i += 1;
+    }
+    let mut delimiter = String::new();
+    while let Some(&c) = chars.get(i) {
+        if c.is_whitespace() || ";|&()<>".contains(c) {
+            break;
+        }
+        if c != '\'' && c != '"' && c != '\\' {
+            delimiter.push(c);
+        }
+        i += 1;
+    }
+    (delimiter, i)
+}
+
+/// Index of the newline ending the here-document that starts at `from`, so
+/// the body is never parsed as commands.
+fn skip_heredoc(chars: &[char], from: usize, delimiter: &str) -> usize {
+    let mut start = from;
+    while start < chars.len() {
+        let end = find(chars, start, '\n');
+        let line: String = chars[start..end].iter().collect();
+        if line.trim() == delimiter {
+            return end;
+        }
+        start = end + 1;
+    }
+    chars.len()
+}
+
+/// Classify one simple command's words, or None if it runs nothing
+/// countable (a bare assignment, a keyword, or a program name built from
+/// variables).
+fn simple_command(words: &[String]) -> Option<BashCommand> {
+    let mut rest = words;
+    loop {
+        let first = rest.first()?.as_str();
+        if KEYWORD_SEGMENTS.contains(&first) {
+            return None;
+        }
+        if KEYWORD_PREFIXES.contains(&first) || is_assignment(first) {
+            rest = &rest[1..];
+        } else if WRAPPERS.contains(&first) {
+            rest = &rest[1..];
+            // Wrapper options, plus the duration `timeout` takes
+            while rest.first().is_some_and(|w| w.starts_with('-')) {
+                rest = &rest[1..];
+            }
+            if first == "timeout" && !rest.is_empty() {
+                rest = &rest[1..];
+            }
+        } else {
+            break;
+        }
+    }
+
+    let program = rest[0].rsplit('/').next().unwrap_or_default().to_string();
+    if program.is_empty() || !program.chars().all(|c| c.is_ascii_alphanumeric() || "_.+-".contains(c)) {
+        return None;
+    }
+    let args = &rest[1..];
+    let subcommand = subcommand(&program, args);
+    let command = match &subcommand {
+        Some(sub) => format!("{program} {sub}"),
+        None => program.clone(),
+    };
+    let category = category(&program, subcommand.as_deref()).map(String::from);
+    Some(BashCommand {
+        position: 0,
+        program,
+        subcommand,
+        command,
+        category,
+    })
+}
+
+/// `FOO=bar`
+fn is_assignment(word: &str) -> bool {
+    match word.split_once('=') {
+        Some((name, _)) => {
+            !name.is_empty()
+                && !name.starts_with(|c: char| c.is_ascii_digit())
+                && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+        }
+        None => false,
+    }
+}
+
+fn subcommand(program: &str, args: &[String]) -> Option<String> {
+    if program.starts_with("python") {
+        let module = args.iter().position(|a| a == "-m")?;
+        return args.get(module + 1).filter(|m| is_plain(m)).map(|m| format!("-m {m}"));
+    }
+    if !SUBCOMMAND_PROGRAMS.contains(&program) {
+        return None;
+    }
+    let mut i = 0;
+    while let Some(arg) = args.get(i) {
+        if program == "git" && (arg == "-C" || arg == "-c") {
+            i += 2;
+        } else if arg.starts_with('-') || arg.starts_with('+') || is_assignment(arg) {
+            i += 1;
+        } else {
+            break;
+        }
+    }
+    let sub = args.get(i).filter(|s| is_plain(s))?;
+    if SCRIPT_RUNNERS.contains(&program) && sub == "run" {
+        if let Some(script) = args.get(i + 1).filter(|s| is_plain(s)) {
+            return Some(format!("run {script}"));
+        }
+    }
+    Some(sub.clone())
+}
+
+/// A literal word such as a subcommand or script name, not a path or a
+/// variable expansion.
+fn is_plain(word: &str) -> bool {
+    !word.is_empty() && word.chars().all(|c| c.is_ascii_alphanumeric() || "_.:-".contains(c))
+}
+
+/// One of test, build, vcs, package, filesystem or network.
+fn category(program: &str, subcommand: Option<&str>) -> Option<&'static str> {
+    let sub = subcommand.unwrap_or_default();
+    let script = sub.strip_prefix("run ").unwrap_or(sub);
+    let category = match program {
+        "pytest" | "jest" | "vitest" | "mocha" | "rspec" | "tox" | "ctest" | "phpunit" | "playwright" => "test",
+        "tsc" | "gcc" | "g++" | "cc" | "clang" | "clang++" | "javac" | "rustc" | "cmake" | "ninja" | "mvn"
+        | "gradle" | "gradlew" | "webpack" | "esbuild" | "vite" => "build",
+        "git" | "gh" | "hg" | "svn" | "jj" => "vcs",
+        "brew" | "apt" | "apt-get" | "gem" | "bundle" | "pip" | "pip3" => "package",
+        "ls" | "cat" | "cd" | "mkdir" | "rm" | "rmdir" | "mv" | "cp" | "touch" | "find" | "chmod" | "chown"
+        | "ln" | "head" | "tail" | "wc" | "tree" | "du" | "df" | "pwd" | "stat" | "file" | "realpath" | "grep"
+        | "rg" | "fd" => "filesystem",
+        "curl" | "wget" | "ssh" | "scp" | "rsync" | "ping" | "nc" | "dig" | "nslookup" | "http" | "telnet" => {
+            "network"
+        }
+        "cargo" => match sub {
+            "test" | "nextest" | "bench" => "test",
+            "build" | "check" | "clippy" | "doc" | "fmt" => "build",
+            "add" | "install" | "update" | "remove" | "fetch" => "package",
+            _ => return None,
+        },
+        "go" => match sub {
+            "test" => "test",
+            "build" | "vet" | "generate" => "build",
+            "get" | "install" | "mod" => "package",
+            _ => return None,
+        },
+        "npm" | "pnpm" | "yarn" | "bun" | "deno" => match script {
+            s if s.starts_with("test") => "test",
+            s if s.starts_with("build") || s == "compile" || s == "typecheck" => "build",
+            "install" | "i" | "ci" | "add" | "remove" | "uninstall" | "update" | "upgrade" => "package",
+            _ => return None,
+        },
+        "make" => match sub {
+            s if s.starts_with("test") || s == "check" => "test",
+            _ => "build",
+        },
+        "docker" => match sub {
+            "build" => "build",
+            "pull" | "push" => "network",
+            _ => return None,
+        },
+        "dotnet" => match sub {
+            "test" => "test",
+            "build" => "build",
+            "add" | "restore" => "package",
+            _ => return None,
+        },
+        "uv" | "poetry" => match sub {
+            "add" | "remove" | "sync" | "install" | "lock" | "pip" => "package",
+            "build" => "build",
+            _ => return None,
+        },
+        p if p.starts_with("python") => match sub {
+            "-m pytest" | "-m unittest" => "test",
+            "-m pip" => "package",
+            _ => return None,
+        },
+        _ => return None,
+    };
+    Some(category)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn commands(command: &str) -> Vec<String> {
+        parse(command).into_iter().map(|c| c.command).collect()
+    }
+
+    #[test]
+    fn splits_pipelines_and_chains() {
+        assert_eq!(
+            commands("cd /repo && cargo test -- foo | tail -20; git status || echo failed"),
+            vec!["cd", "cargo test", "tail", "git status", "echo"]
+        );
+        assert_eq!(commands("(cd web && npm run build) & wait"), vec!["cd", "npm run build", "wait"]);
+        assert_eq!(commands("make\nmake -j4 test"), vec!["make", "make test"]);
+    }
+
+    #[test]
+    fn quotes_and_substitutions_stay_in_one_word() {
+        assert_eq!(
+            commands(r#"git commit -m "fix: a && b; c | d" && echo 'x | y'"#),
+            vec!["git commit", "echo"]
+        );
+        assert_eq!(
+            commands("git commit -m \"$(cat <<'EOF'\nSubject (with parens)\n\nBody; more\nEOF\n)\""),
+            vec!["git commit"]
+        );
+        assert_eq!(commands("echo `date | cut -c1`"), vec!["echo"]);
+    }
+
+    #[test]
+    fn redirections_and_heredocs_are_dropped() {
+        assert_eq!(commands("cargo build 2>&1 > /tmp/log.txt"), vec!["cargo build"]);
+        assert_eq!(commands("cat > notes.md <<'EOF'\nrm -rf /\nls | wc\nEOF\ngit add notes.md"), vec!["cat", "git add"]);
+        assert_eq!(commands("npm test &> out.log"), vec!["npm test"]);
+    }
+
+    #[test]
+    fn skips_assignments_wrappers_and_keywords() {
+        assert_eq!(commands("RUST_LOG=debug cargo run --release"), vec!["cargo run"]);
+        assert_eq!(commands("sudo -E env FOO=1 timeout 60 pytest -x"), vec!["pytest"]);
+        assert_eq!(commands("if cargo test; then echo ok; fi"), vec!["cargo test", "echo"]);
+        assert_eq!(commands("for f in *.rs; do wc -l $f; done"), vec!["wc"]);
+        assert_eq!(commands("FOO=1"), Vec::<String>::new());
+        assert_eq!(commands("$EDITOR file"), Vec::<String>::new());
+        assert_eq!(commands("# just a comment"), Vec::<String>::new());
+    }
+
+    #[test]
+    fn normalizes_program_and_subcommand() {
+        assert_eq!(commands("/usr/bin/git -C ../other log --oneline"), vec!["git log"]);
+        assert_eq!(commands("cargo +nightly fmt --all"), vec!["cargo fmt"]);
+        assert_eq!(commands("python3 -m pytest tests/"), vec!["python3 -m pytest"]);
+        assert_eq!(commands("npm run \"$SCRIPT\""), vec!["npm run"]);
+        assert_eq!(commands("./scripts/deploy.sh prod"), vec!["deploy.sh"]);
+        let parsed = parse("ls && npm run build:prod");
+        assert_eq!(parsed[1].position, 1);
+        assert_eq!(parsed[1].program, "npm");
+        assert_eq!(parsed[1].subcommand.as_deref(), Some("run build:prod"));
+    }
+
+    #[test]
+    fn categories() {
+        let category = |command: &str| parse(command).remove(0).category;
+        assert_eq!(category("cargo test").as_deref(), Some("test"));
+        assert_eq!(category("npm run test:unit").as_deref(), Some("test"));
+        assert_eq!(category("go build ./...").as_deref(), Some("build"));
+        assert_eq!(category("git push").as_deref(), Some("vcs"));
+        assert_eq!(category("pnpm add zod").as_deref(), Some("package"));
+        assert_eq!(category("rg TODO src").as_deref(), Some("filesystem"));
+        assert_eq!(category("curl -sS https://example.com").as_deref(), Some("network"));
+        assert_eq!(category("echo hi"), None);
+        assert_eq!(category("cargo run"), None);
+    }
+}
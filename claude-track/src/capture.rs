@@ -0,0 +1,253 @@
+use rusqlite::Connection;
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+
+use crate::config::{CaptureConfig, CaptureLevel};
+use crate::db;
+use crate::redact::TRANSCRIPT_CONTENT_FIELDS;
+
+/// Hook payload fields whose content the capture level applies to.
+const CAPTURE_FIELDS: &[&str] = &[
+    "prompt",
+    "tool_input",
+    "tool_response",
+    "custom_instructions",
+    "last_assistant_message",
+];
+
+/// Keys inside tool inputs and responses that describe a call rather than
+/// carry its content, kept at every level so stats can still group by them.
+const METADATA_KEYS: &[&str] = &["subagent_type", "model", "type", "status", "mode", "output_mode"];
+
+/// `METADATA_KEYS` plus the keys inside transcript messages that link and
+/// classify lines, so a transcript captured below `full` still parses.
+const TRANSCRIPT_METADATA_KEYS: &[&str] = &[
+    "subagent_type",
+    "model",
+    "type",
+    "status",
+    "mode",
+    "output_mode",
+    "id",
+    "tool_use_id",
+    "role",
+    "name",
+    "stop_reason",
+];
+
+/// Apply the capture level for the event's project to a raw hook payload.
+/// At `hashed`, every string in the captured fields becomes
+/// `sha256:<salted hash>:<length in chars>`; at `none`, it becomes empty.
+/// Numbers, booleans and `METADATA_KEYS` are kept, so tool outcomes and
+/// subagent types still classify. The payload gains a `capture_level` field.
+pub fn apply(raw: &str, config: &CaptureConfig, conn: &Connection) -> Result<String, Box<dyn std::error::Error>> {
+    let Ok(Value::Object(mut map)) = serde_json::from_str::<Value>(raw) else {
+        return Ok(raw.to_string());
+    };
+    let cwd = match map.get("cwd").and_then(|c| c.as_str()) {
+        Some(cwd) => Some(cwd.to_string()),
+        None => {
+            let session_id = map.get("session_id").and_then(|s| s.as_str()).unwrap_or_default();
+            db::get_session_cwd(conn, session_id)?
+        }
+    };
+    let level = config.level_for(cwd.as_deref().unwrap_or_default());
+    if level == CaptureLevel::Full || !CAPTURE_FIELDS.iter().any(|f| map.contains_key(*f)) {
+        return Ok(raw.to_string());
+    }
+
+    let salt = match level {
+        CaptureLevel::Hashed => db::capture_salt(conn)?,
+        _ => String::new(),
+    };
+    for field in CAPTURE_FIELDS {
+        if let Some(value) = map.get_mut(*field) {
+            strip(value, level, &salt, METADATA_KEYS);
+        }
+    }
+    map.insert("capture_level".to_string(), Value::String(level.as_str().to_string()));
+    Ok(Value::Object(map).to_string())
+}
+
+fn strip(value: &mut Value, level: CaptureLevel, salt: &str, keep: &[&str]) {
+    match value {
+        Value::String(s) => {
+            *s = match level {
+                CaptureLevel::Hashed => hashed(s, salt),
+                _ => String::new(),
+            }
+        }
+        Value::Array(items) => items.iter_mut().for_each(|v| strip(v, level, salt, keep)),
+        Value::Object(map) => {
+            for (key, v) in map.iter_mut() {
+                if !keep.contains(&key.as_str()) {
+                    strip(v, level, salt, keep);
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Capture level for a session's events, from the working directory it
+/// started in.
+pub fn level_for_session(config: &CaptureConfig, conn: &Connection, session_id: &str) -> CaptureLevel {
+    let cwd = db::get_session_cwd(conn, session_id).ok().flatten().unwrap_or_default();
+    config.level_for(&cwd)
+}
+
+/// Apply `level` to text read from a transcript rather than a hook payload,
+/// the same way `apply` treats payload strings.
+pub fn capture_text(conn: &Connection, level: CaptureLevel, text: &str) -> Result<String, Box<dyn std::error::Error>> {
+    Ok(match level {
+        CaptureLevel::Full => text.to_string(),
+        CaptureLevel::Hashed => hashed(text, &db::capture_salt(conn)?),
+        CaptureLevel::None => String::new(),
+    })
+}
+
+/// Apply `level` to the content fields of one transcript line (no trailing
+/// newline), keeping ids, types and token counts. A line that isn't a JSON
+/// object is captured as plain text.
+pub fn capture_transcript_line(
+    conn: &Connection,
+    level: CaptureLevel,
+    line: &str,
+) -> Result<String, Box<dyn std::error::Error>> {
+    if level == CaptureLevel::Full {
+        return Ok(line.to_string());
+    }
+    let Ok(Value::Object(mut map)) = serde_json::from_str::<Value>(line) else {
+        return capture_text(conn, level, line);
+    };
+    let salt = match level {
+        CaptureLevel::Hashed => db::capture_salt(conn)?,
+        _ => String::new(),
+    };
+    for field in TRANSCRIPT_CONTENT_FIELDS {
+        if let Some(value) = map.get_mut(*field) {
+            strip(value, level, &salt, TRANSCRIPT_METADATA_KEYS);
+        }
+    }
+    Ok(Value::Object(map).to_string())
+}
+
+/// `sha256:<hex of salt + text>:<length in chars>`. Text that is already a
+/// hashed value is kept, so a line archived at `hashed` isn't hashed twice.
+fn hashed(text: &str, salt: &str) -> String {
+    if hashed_length(text).is_some() {
+        return text.to_string();
+    }
+    let mut hasher = Sha256::new();
+    hasher.update(salt.as_bytes());
+    hasher.update(text.as_bytes());
+    let hex: String = hasher.finalize().iter().map(|b| format!("{b:02x}")).collect();
+    format!("sha256:{hex}:{}", text.chars().count())
+}
+
+/// Length recorded in a hashed value, or None if `text` isn't one.
+pub fn hashed_length(text: &str) -> Option<i64> {
+    let rest = text.strip_prefix("sha256:")?;
+    let (hex, len) = rest.split_once(':')?;
+    if hex.len() != 64 || !hex.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return None;
+    }
+    len.parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::ProjectCapture;
+
+    fn test_conn() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        db::init_db(&conn).unwrap();
+        conn
+    }
+
+    fn config(level: CaptureLevel) -> CaptureConfig {
+        CaptureConfig {
+            level: CaptureLevel::Full,
+            projects: vec![ProjectCapture {
+                path: "/work/client-*".into(),
+                level,
+            }],
+        }
+    }
+
+    #[test]
+    fn full_leaves_payload_unchanged() {
+        let conn = test_conn();
+        let raw = r#"{"cwd":"/home/me/oss","prompt":"hello"}"#;
+        assert_eq!(apply(raw, &config(CaptureLevel::None), &conn).unwrap(), raw);
+    }
+
+    #[test]
+    fn hashed_keeps_structure_and_length() {
+        let conn = test_conn();
+        let raw = r#"{"cwd":"/work/client-a/api","session_id":"s1","prompt":"héllo","tool_input":{"subagent_type":"Explore","prompt":"look","timeout":5}}"#;
+        let out: Value = serde_json::from_str(&apply(raw, &config(CaptureLevel::Hashed), &conn).unwrap()).unwrap();
+        let prompt = out["prompt"].as_str().unwrap();
+        assert!(prompt.starts_with("sha256:"));
+        assert_eq!(hashed_length(prompt), Some(5));
+        assert_eq!(out["session_id"], "s1");
+        assert_eq!(out["tool_input"]["subagent_type"], "Explore");
+        assert_eq!(out["tool_input"]["timeout"], 5);
+        assert_eq!(hashed_length(out["tool_input"]["prompt"].as_str().unwrap()), Some(4));
+        assert_eq!(out["capture_level"], "hashed");
+
+        // Same text, same salt, same hash
+        let again: Value = serde_json::from_str(&apply(raw, &config(CaptureLevel::Hashed), &conn).unwrap()).unwrap();
+        assert_eq!(again["prompt"], out["prompt"]);
+    }
+
+    #[test]
+    fn none_drops_content_and_uses_session_cwd() {
+        let conn = test_conn();
+        db::insert_session_start(&conn, "s1", "t", "startup", "/work/client-b", "/t").unwrap();
+        let raw = r#"{"session_id":"s1","tool_response":{"stdout":"secret","interrupted":true}}"#;
+        let out: Value = serde_json::from_str(&apply(raw, &config(CaptureLevel::None), &conn).unwrap()).unwrap();
+        assert_eq!(out["tool_response"]["stdout"], "");
+        assert_eq!(out["tool_response"]["interrupted"], true);
+        assert_eq!(out["capture_level"], "none");
+    }
+
+    #[test]
+    fn transcript_text_follows_session_level() {
+        let conn = test_conn();
+        db::insert_session_start(&conn, "s1", "t", "startup", "/work/client-a", "/t").unwrap();
+        let level = level_for_session(&config(CaptureLevel::Hashed), &conn, "s1");
+        assert_eq!(level, CaptureLevel::Hashed);
+        assert_eq!(hashed_length(&capture_text(&conn, level, "abc").unwrap()), Some(3));
+        assert_eq!(capture_text(&conn, CaptureLevel::None, "abc").unwrap(), "");
+        assert_eq!(level_for_session(&config(CaptureLevel::Hashed), &conn, "other"), CaptureLevel::Full);
+    }
+
+    #[test]
+    fn transcript_line_keeps_ids_and_usage() {
+        let conn = test_conn();
+        let line = r#"{"type":"assistant","uuid":"u1","message":{"id":"msg_1","role":"assistant","model":"claude-opus-4-6","content":[{"type":"tool_use","id":"toolu_1","name":"Bash","input":{"command":"ls secret"}}],"usage":{"output_tokens":7}}}"#;
+        let out: Value = serde_json::from_str(&capture_transcript_line(&conn, CaptureLevel::Hashed, line).unwrap()).unwrap();
+        let message = &out["message"];
+        assert_eq!(out["uuid"], "u1");
+        assert_eq!(message["id"], "msg_1");
+        assert_eq!(message["model"], "claude-opus-4-6");
+        assert_eq!(message["usage"]["output_tokens"], 7);
+        assert_eq!(message["content"][0]["id"], "toolu_1");
+        assert_eq!(message["content"][0]["name"], "Bash");
+        let command = message["content"][0]["input"]["command"].as_str().unwrap();
+        assert_eq!(hashed_length(command), Some(9));
+
+        // Capturing an already hashed line changes nothing
+        let again = capture_transcript_line(&conn, CaptureLevel::Hashed, &out.to_string()).unwrap();
+        assert_eq!(serde_json::from_str::<Value>(&again).unwrap(), out);
+        assert_eq!(capture_transcript_line(&conn, CaptureLevel::Full, line).unwrap(), line);
+    }
+
+    #[test]
+    fn hashed_length_rejects_plain_text() {
+        assert_eq!(hashed_length("sha256:abc:3"), None);
+        assert_eq!(hashed_length("hello"), None);
+    }
+}
@@ -2,7 +2,11 @@ use std::path::{Path, PathBuf};
 
 use rusqlite::Connection;
 
+use crate::bash_command;
+use crate::capture;
+use crate::config::{self, CaptureConfig};
 use crate::db;
+use crate::tool_result::{self, PlanOutcome};
 
 /// A plan discovered from a transcript file.
 #[derive(Debug)]
@@ -11,9 +15,12 @@ struct DiscoveredPlan {
     tool_use_id: String,
     timestamp: String,
     plan_text: String,
+    /// From the plan's tool_result, if the transcript has one.
+    outcome: Option<PlanOutcome>,
 }
 
-/// Backfill plans from historical transcript files.
+/// Backfill plans, and whether they were accepted, from historical transcript
+/// files, and parse Bash commands recorded before they were parsed at the hook.
 #[cfg(not(tarpaulin_include))]
 pub fn run() {
     if let Err(e) = try_run() {
@@ -28,30 +35,60 @@ fn try_run() -> Result<(), Box<dyn std::error::Error>> {
     let db_path = home.join(".claude").join("claude-track.db");
 
     let conn = db::open_db(&db_path)?;
-    let output = backfill_from(&projects_dir, &conn)?;
+    let config = config::load()?;
+    let output = backfill_from(&projects_dir, &config.capture, &conn)?;
     print!("{output}");
     Ok(())
 }
 
 /// Scan transcript files under `projects_dir` and import plans into the database.
+/// Plan text and feedback are stored at each session's capture level.
 /// Returns user-facing summary output.
 pub fn backfill_from(
     projects_dir: &Path,
+    capture_config: &CaptureConfig,
     conn: &Connection,
 ) -> Result<String, Box<dyn std::error::Error>> {
-    if !projects_dir.exists() {
+    let parsed = backfill_bash_commands(conn)?;
+    let mut parsed_line = match parsed {
+        0 => String::new(),
+        n => format!("Parsed commands from {n} earlier Bash calls.\n"),
+    };
+    let measured = db::get_sessions_without_context_limits(conn)?;
+    for session_id in &measured {
+        db::update_context_usage(conn, session_id)?;
+    }
+    if !measured.is_empty() {
+        parsed_line.push_str(&format!("Measured context use for {} earlier sessions.\n", measured.len()));
+    }
+
+    // Archived copies stand in for transcripts Claude Code has since pruned
+    let mut transcripts = find_transcripts(projects_dir);
+    for session_id in db::get_archived_session_ids(conn)? {
+        let pruned = !transcripts
+            .iter()
+            .any(|t| t.file_stem().and_then(|s| s.to_str()) == Some(session_id.as_str()));
+        if pruned {
+            if let Some(restored) = crate::archive::restore_transcript(conn, &session_id)? {
+                transcripts.push(restored);
+            }
+        }
+    }
+
+    if !projects_dir.exists() && transcripts.is_empty() {
         return Ok(format!(
-            "No projects directory found at {}\nNothing to backfill.\n",
+            "{parsed_line}No projects directory found at {}\nNothing to backfill.\n",
             projects_dir.display()
         ));
     }
 
-    let transcripts = find_transcripts(projects_dir);
     let mut existing_ids = db::get_all_plan_tool_use_ids(conn)?;
+    let pending_ids = db::get_pending_plan_ids(conn, None)?;
 
     let mut total_found = 0u64;
     let mut total_imported = 0u64;
     let mut total_skipped = 0u64;
+    let mut total_resolved = 0u64;
 
     for transcript in &transcripts {
         let session_id = transcript
@@ -61,21 +98,33 @@ pub fn backfill_from(
 
         let plans = extract_plans_from_transcript(transcript, session_id);
 
+        let level = capture::level_for_session(capture_config, conn, session_id);
         for plan in plans {
             total_found += 1;
             if existing_ids.contains(&plan.tool_use_id) {
                 total_skipped += 1;
-                continue;
+                if !pending_ids.contains(&plan.tool_use_id) {
+                    continue;
+                }
+            } else {
+                db::insert_plan(
+                    conn,
+                    &plan.session_id,
+                    &plan.tool_use_id,
+                    &plan.timestamp,
+                    &capture::capture_text(conn, level, &plan.plan_text)?,
+                )?;
+                existing_ids.insert(plan.tool_use_id.clone());
+                total_imported += 1;
+            }
+            if let Some(outcome) = &plan.outcome {
+                let feedback = match &outcome.feedback {
+                    Some(text) => Some(capture::capture_text(conn, level, text)?).filter(|f| !f.is_empty()),
+                    None => None,
+                };
+                db::set_plan_outcome(conn, &plan.tool_use_id, outcome.accepted, feedback.as_deref())?;
+                total_resolved += 1;
             }
-            db::insert_plan(
-                conn,
-                &plan.session_id,
-                &plan.tool_use_id,
-                &plan.timestamp,
-                &plan.plan_text,
-            )?;
-            existing_ids.insert(plan.tool_use_id);
-            total_imported += 1;
         }
     }
 
@@ -86,12 +135,32 @@ pub fn backfill_from(
         total_imported,
         total_skipped,
     );
+    if total_resolved > 0 {
+        output.push_str(&format!("Recorded whether {total_resolved} plans were accepted.\n"));
+    }
     if transcripts.is_empty() {
         output.push_str("No transcript files found.\n");
     }
+    output.push_str(&parsed_line);
     Ok(output)
 }
 
+/// Parse the commands of Bash calls that have none stored. Returns how many
+/// calls were parsed.
+fn backfill_bash_commands(conn: &Connection) -> Result<usize, Box<dyn std::error::Error>> {
+    let calls = db::get_unparsed_bash_calls(conn)?;
+    let mut parsed = 0;
+    for (tool_use_id, session_id, timestamp, cwd, command) in calls {
+        let commands = bash_command::parse(&command);
+        if commands.is_empty() {
+            continue;
+        }
+        db::insert_bash_commands(conn, &tool_use_id, &session_id, &timestamp, &cwd, &commands)?;
+        parsed += 1;
+    }
+    Ok(parsed)
+}
+
 /// Find all *.jsonl transcript files under project subdirectories.
 /// Returns a sorted list for deterministic processing.
 fn find_transcripts(projects_dir: &Path) -> Vec<PathBuf> {
@@ -121,7 +190,8 @@ fn find_transcripts(projects_dir: &Path) -> Vec<PathBuf> {
 }
 
 /// Extract ExitPlanMode plans from a transcript file.
-/// Scans assistant lines for ExitPlanMode tool_use blocks.
+/// Scans assistant lines for ExitPlanMode tool_use blocks, and user lines
+/// for their tool_result to tell whether each plan was accepted.
 fn extract_plans_from_transcript(path: &Path, session_id: &str) -> Vec<DiscoveredPlan> {
     let content = match std::fs::read_to_string(path) {
         Ok(c) => c,
@@ -129,6 +199,7 @@ fn extract_plans_from_transcript(path: &Path, session_id: &str) -> Vec<Discovere
     };
 
     let mut plans = Vec::new();
+    let mut results = Vec::new();
 
     for line in content.lines() {
         if line.is_empty() {
@@ -139,6 +210,14 @@ fn extract_plans_from_transcript(path: &Path, session_id: &str) -> Vec<Discovere
             Err(_) => continue,
         };
 
+        if val.get("type").and_then(|v| v.as_str()) == Some("user") {
+            if let Some(content) = val.get("message").and_then(|m| m.get("content")) {
+                // A result always follows its tool_use, so only plans seen so far matter
+                let is_plan = |id: &str| plans.iter().any(|p: &DiscoveredPlan| p.tool_use_id == id);
+                results.extend(tool_result::tool_results(content).into_iter().filter(|r| is_plan(&r.tool_use_id)));
+            }
+            continue;
+        }
         if val.get("type").and_then(|v| v.as_str()) != Some("assistant") {
             continue;
         }
@@ -180,16 +259,24 @@ fn extract_plans_from_transcript(path: &Path, session_id: &str) -> Vec<Discovere
                 tool_use_id: id,
                 timestamp: timestamp.clone(),
                 plan_text,
+                outcome: None,
             });
         }
     }
 
+    for plan in &mut plans {
+        plan.outcome = results
+            .iter()
+            .find(|r| r.tool_use_id == plan.tool_use_id)
+            .map(|r| tool_result::plan_outcome(r.is_error, &r.text));
+    }
     plans
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::models::ApiCall;
     use std::fs;
     use tempfile::TempDir;
 
@@ -215,8 +302,46 @@ mod tests {
         .to_string()
     }
 
+    fn make_result_line(tool_use_id: &str, is_error: bool, text: &str) -> String {
+        serde_json::json!({
+            "type": "user",
+            "message": {
+                "content": [{
+                    "type": "tool_result",
+                    "tool_use_id": tool_use_id,
+                    "is_error": is_error,
+                    "content": text
+                }]
+            }
+        })
+        .to_string()
+    }
+
     // --- extract_plans_from_transcript tests ---
 
+    #[test]
+    fn extract_plan_outcomes() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("s.jsonl");
+        let content = format!(
+            "{}\n{}\n{}\n{}\n{}\n",
+            make_assistant_line("toolu_1", "plan 1", "2026-01-01T00:00:00Z"),
+            make_result_line("toolu_1", true, "The user doesn't want to proceed with this tool use. To tell you how to proceed, the user said:\nsmaller"),
+            make_assistant_line("toolu_2", "plan 2", "2026-01-01T01:00:00Z"),
+            make_result_line("toolu_2", false, "User has approved your plan."),
+            make_assistant_line("toolu_3", "plan 3", "2026-01-01T02:00:00Z"),
+        );
+        fs::write(&path, content).unwrap();
+
+        let plans = extract_plans_from_transcript(&path, "s1");
+        assert_eq!(
+            plans[0].outcome,
+            Some(PlanOutcome { accepted: false, feedback: Some("smaller".into()) })
+        );
+        assert_eq!(plans[1].outcome, Some(PlanOutcome { accepted: true, feedback: None }));
+        assert_eq!(plans[2].outcome, None);
+    }
+
     #[test]
     fn extract_single_plan() {
         let dir = TempDir::new().unwrap();
@@ -496,16 +621,53 @@ mod tests {
     #[test]
     fn backfill_no_projects_dir() {
         let conn = test_conn();
-        let output = backfill_from(Path::new("/nonexistent/projects"), &conn).unwrap();
+        let output = backfill_from(Path::new("/nonexistent/projects"), &CaptureConfig::default(), &conn).unwrap();
         assert!(output.contains("No projects directory found"));
         assert!(output.contains("Nothing to backfill"));
     }
 
+    #[test]
+    fn backfill_parses_earlier_bash_calls() {
+        let conn = test_conn();
+        db::insert_tool_use(&conn, "tu1", "s1", "Bash", "ts", "ts", "/p", r#"{"command":"cargo build && cargo test"}"#)
+            .unwrap();
+        let output = backfill_from(Path::new("/nonexistent/projects"), &CaptureConfig::default(), &conn).unwrap();
+        assert!(output.starts_with("Parsed commands from 1 earlier Bash calls."), "{output}");
+        let count: i64 = conn.query_row("SELECT COUNT(*) FROM bash_commands", [], |r| r.get(0)).unwrap();
+        assert_eq!(count, 2);
+
+        // Already parsed calls are left alone
+        let output = backfill_from(Path::new("/nonexistent/projects"), &CaptureConfig::default(), &conn).unwrap();
+        assert!(!output.contains("Parsed commands"));
+    }
+
+    #[test]
+    fn backfill_measures_context_of_earlier_sessions() {
+        let conn = test_conn();
+        let call = ApiCall {
+            message_id: "msg_1".into(),
+            model: "claude-opus-4-6".into(),
+            input_tokens: 10,
+            cache_read_tokens: 170_000,
+            ..Default::default()
+        };
+        db::upsert_api_call(&conn, "s1", &call).unwrap();
+        let output = backfill_from(Path::new("/nonexistent/projects"), &CaptureConfig::default(), &conn).unwrap();
+        assert!(output.starts_with("Measured context use for 1 earlier sessions."), "{output}");
+        let (limit, high): (i64, bool) = conn
+            .query_row("SELECT context_limit, context_high FROM api_calls", [], |r| Ok((r.get(0)?, r.get(1)?)))
+            .unwrap();
+        assert_eq!((limit, high), (200_000, true));
+
+        let output = backfill_from(Path::new("/nonexistent/projects"), &CaptureConfig::default(), &conn).unwrap();
+        assert!(!output.contains("Measured context"));
+    }
+
     #[test]
     fn backfill_empty_projects_dir() {
         let dir = TempDir::new().unwrap();
         let conn = test_conn();
-        let output = backfill_from(dir.path(), &conn).unwrap();
+        let output = backfill_from(dir.path(), &CaptureConfig::default(), &conn).unwrap();
         assert!(output.contains("Scanned 0 transcript files"));
         assert!(output.contains("No transcript files found"));
     }
@@ -523,7 +685,7 @@ mod tests {
         fs::write(sub.join("sess123.jsonl"), content).unwrap();
 
         let conn = test_conn();
-        let output = backfill_from(dir.path(), &conn).unwrap();
+        let output = backfill_from(dir.path(), &CaptureConfig::default(), &conn).unwrap();
         assert!(output.contains("1 imported"));
 
         // Verify in DB
@@ -537,6 +699,55 @@ mod tests {
         assert_eq!(plan_text, "my plan");
     }
 
+    #[test]
+    fn backfill_resolves_existing_pending_plans() {
+        let dir = TempDir::new().unwrap();
+        let sub = dir.path().join("project1");
+        fs::create_dir_all(&sub).unwrap();
+        let content = format!(
+            "{}\n{}\n",
+            make_assistant_line("toolu_1", "my plan", "2026-01-01T00:00:00Z"),
+            make_result_line("toolu_1", false, "User has approved your plan."),
+        );
+        fs::write(sub.join("sess123.jsonl"), content).unwrap();
+
+        let conn = test_conn();
+        // Recorded live by PreToolUse, outcome never seen
+        db::insert_plan(&conn, "sess123", "toolu_1", "2026-01-01T00:00:00Z", "my plan").unwrap();
+        let output = backfill_from(dir.path(), &CaptureConfig::default(), &conn).unwrap();
+        assert!(output.contains("1 skipped"));
+        assert!(output.contains("Recorded whether 1 plans were accepted."));
+        let accepted: bool = conn.query_row("SELECT accepted FROM plans", [], |r| r.get(0)).unwrap();
+        assert!(accepted);
+
+        // Already resolved: left alone
+        let output = backfill_from(dir.path(), &CaptureConfig::default(), &conn).unwrap();
+        assert!(!output.contains("Recorded whether"));
+    }
+
+    #[test]
+    fn backfill_reads_archived_copy_of_pruned_transcript() {
+        let dir = TempDir::new().unwrap();
+        let conn = db::open_db(&dir.path().join("claude-track.db")).unwrap();
+        let projects = dir.path().join("projects");
+        let sub = projects.join("project1");
+        fs::create_dir_all(&sub).unwrap();
+        let transcript = sub.join("sess123.jsonl");
+        let content = format!("{}\n", make_assistant_line("toolu_1", "my plan", "2026-01-01T00:00:00Z"));
+        fs::write(&transcript, content).unwrap();
+        let redactor = crate::redact::Redactor::new(&crate::config::RedactionConfig::default()).unwrap();
+        crate::archive::archive_transcript(&conn, "sess123", &transcript, &redactor, crate::config::CaptureLevel::Full).unwrap();
+        fs::remove_file(&transcript).unwrap();
+
+        let output = backfill_from(&projects, &CaptureConfig::default(), &conn).unwrap();
+        assert!(output.contains("Scanned 1 transcript files"));
+        assert!(output.contains("1 imported"));
+        let session_id: String = conn
+            .query_row("SELECT session_id FROM plans WHERE tool_use_id='toolu_1'", [], |r| r.get(0))
+            .unwrap();
+        assert_eq!(session_id, "sess123");
+    }
+
     #[test]
     fn backfill_deduplication() {
         let dir = TempDir::new().unwrap();
@@ -553,7 +764,7 @@ mod tests {
         // Pre-insert the plan
         db::insert_plan(&conn, "sess123", "toolu_1", "2026-01-01T00:00:00Z", "my plan").unwrap();
 
-        let output = backfill_from(dir.path(), &conn).unwrap();
+        let output = backfill_from(dir.path(), &CaptureConfig::default(), &conn).unwrap();
         assert!(output.contains("1 skipped"));
         assert!(output.contains("0 imported"));
 
@@ -577,7 +788,7 @@ mod tests {
         fs::write(sub.join("my-session-uuid.jsonl"), content).unwrap();
 
         let conn = test_conn();
-        backfill_from(dir.path(), &conn).unwrap();
+        backfill_from(dir.path(), &CaptureConfig::default(), &conn).unwrap();
 
         let session_id: String = conn
             .query_row(
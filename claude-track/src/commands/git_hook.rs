@@ -0,0 +1,201 @@
+use std::path::Path;
+
+use chrono::{DateTime, Duration, Utc};
+use rusqlite::Connection;
+
+use crate::attribution;
+use crate::commands::hook;
+use crate::config::{self, Config};
+use crate::db;
+use crate::git;
+
+/// Sessions with no activity for this long aren't considered active, even if
+/// they never recorded a SessionEnd.
+const ACTIVE_SESSION_HOURS: i64 = 12;
+
+/// Agent edits made this long before a commit count toward its attribution.
+const ATTRIBUTION_WINDOW_HOURS: i64 = 24;
+
+/// Git hook entrypoint, called by the hooks `install --git` writes. Like
+/// `hook`, always exits 0 so it never blocks a commit.
+#[cfg(not(tarpaulin_include))]
+pub fn run(name: &str, args: &[String]) {
+    if let Err(e) = try_run(name, args) {
+        eprintln!("claude-track git-hook: {e}");
+    }
+}
+
+#[cfg(not(tarpaulin_include))]
+fn try_run(name: &str, args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    let db_path = db::db_path()?;
+    let conn = db::open_db(&db_path)?;
+    let cwd = std::env::current_dir()?;
+    match name {
+        "prepare-commit-msg" => {
+            let msg_file = args.first().ok_or("missing commit message file")?;
+            prepare_commit_msg(&conn, &cwd, Path::new(msg_file), Utc::now())
+        }
+        "post-commit" => {
+            let config = config::load().unwrap_or_default();
+            post_commit(&conn, &cwd, &config)
+        }
+        _ => Err(format!("unknown git hook '{name}'").into()),
+    }
+}
+
+/// Add a `Claude-Track-Session` trailer for the session active in this
+/// worktree, if there is one.
+pub fn prepare_commit_msg(
+    conn: &Connection,
+    dir: &Path,
+    msg_file: &Path,
+    now: DateTime<Utc>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let worktree = git::toplevel(dir).ok_or("not inside a git work tree")?;
+    let since = (now - Duration::hours(ACTIVE_SESSION_HOURS))
+        .format("%Y-%m-%dT%H:%M:%SZ")
+        .to_string();
+    if let Some(session_id) = db::active_session(conn, &worktree, &since)? {
+        git::add_session_trailer(dir, msg_file, &session_id).ok_or("git interpret-trailers failed")?;
+    }
+    Ok(())
+}
+
+/// Record the new HEAD commit with its agent-vs-human line attribution. It
+/// goes through the event log as a `GitCommit` event so `replay` can rebuild
+/// the `commits` table.
+pub fn post_commit(conn: &Connection, dir: &Path, config: &Config) -> Result<(), Box<dyn std::error::Error>> {
+    let worktree = git::toplevel(dir).ok_or("not inside a git work tree")?;
+    let mut commit = git::commit_info(dir, "HEAD").ok_or("could not read HEAD commit")?;
+
+    let committed_at = DateTime::parse_from_rfc3339(&commit.committed_at)
+        .map(|t| t.with_timezone(&Utc))
+        .unwrap_or_else(|_| Utc::now());
+    let since = (committed_at - Duration::hours(ATTRIBUTION_WINDOW_HOURS))
+        .format("%Y-%m-%dT%H:%M:%SZ")
+        .to_string();
+    let diffs = attribution::parse_diff(&git::commit_diff(dir, "HEAD").unwrap_or_default());
+    let agent = attribution::agent_lines(&db::file_edits_since(conn, &since)?);
+    commit.attribution = Some(attribution::attribute(&worktree, &diffs, &agent));
+
+    let event = serde_json::json!({
+        "hook_event_name": "GitCommit",
+        "session_id": git::session_trailer(&commit.message).unwrap_or_default(),
+        "cwd": worktree,
+        "commit": commit,
+    });
+    hook::dispatch(event.to_string().as_bytes(), config, conn)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::process::Command;
+    use tempfile::TempDir;
+
+    fn test_conn() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        db::init_db(&conn).unwrap();
+        conn
+    }
+
+    fn git(dir: &Path, args: &[&str]) {
+        let ok = Command::new("git")
+            .arg("-C")
+            .arg(dir)
+            .args(["-c", "user.name=t", "-c", "user.email=t@t", "-c", "commit.gpgsign=false"])
+            .args(args)
+            .output()
+            .unwrap()
+            .status
+            .success();
+        assert!(ok, "git {args:?} failed");
+    }
+
+    #[test]
+    fn commit_linked_to_active_session() {
+        let dir = TempDir::new().unwrap();
+        let repo = dir.path().canonicalize().unwrap();
+        git(&repo, &["init", "-q", "-b", "main"]);
+        let conn = test_conn();
+        let now = Utc::now();
+        let started = (now - Duration::minutes(5)).format("%Y-%m-%dT%H:%M:%SZ").to_string();
+        db::insert_session_start(&conn, "s1", &started, "startup", repo.to_str().unwrap(), "/t").unwrap();
+
+        let msg = repo.join(".git").join("COMMIT_EDITMSG");
+        fs::write(&msg, "Add a\n").unwrap();
+        prepare_commit_msg(&conn, &repo, &msg, now).unwrap();
+        assert!(fs::read_to_string(&msg).unwrap().contains("Claude-Track-Session: s1"));
+
+        fs::write(repo.join("a.txt"), "a\n").unwrap();
+        git(&repo, &["add", "a.txt"]);
+        git(&repo, &["commit", "-q", "-F", msg.to_str().unwrap()]);
+        post_commit(&conn, &repo, &Config::default()).unwrap();
+
+        let (session, message, path): (String, String, String) = conn
+            .query_row(
+                "SELECT c.session_id, c.message, f.path FROM commits c JOIN commit_files f ON f.commit_sha = c.sha",
+                [],
+                |r| Ok((r.get(0)?, r.get(1)?, r.get(2)?)),
+            )
+            .unwrap();
+        assert_eq!(session, "s1");
+        assert!(message.starts_with("Add a"));
+        assert_eq!(path, "a.txt");
+        let (agent, human, pct): (i64, i64, f64) = conn
+            .query_row("SELECT agent_lines, human_added, agent_percentage FROM commits", [], |r| {
+                Ok((r.get(0)?, r.get(1)?, r.get(2)?))
+            })
+            .unwrap();
+        assert_eq!((agent, human, pct), (0, 1, 0.0));
+        let logged: i64 = conn
+            .query_row("SELECT COUNT(*) FROM events WHERE event_name = 'GitCommit'", [], |r| r.get(0))
+            .unwrap();
+        assert_eq!(logged, 1);
+    }
+
+    #[test]
+    fn no_trailer_without_active_session() {
+        let dir = TempDir::new().unwrap();
+        git(dir.path(), &["init", "-q"]);
+        let conn = test_conn();
+        // Open, but idle for longer than ACTIVE_SESSION_HOURS
+        db::insert_session_start(&conn, "stale", "2020-01-01T00:00:00Z", "startup", dir.path().to_str().unwrap(), "/t")
+            .unwrap();
+
+        let msg = dir.path().join("msg");
+        fs::write(&msg, "Human commit\n").unwrap();
+        prepare_commit_msg(&conn, dir.path(), &msg, Utc::now()).unwrap();
+        assert_eq!(fs::read_to_string(&msg).unwrap(), "Human commit\n");
+    }
+
+    #[test]
+    fn commit_attributes_lines_to_agent_edits() {
+        let dir = TempDir::new().unwrap();
+        let repo = dir.path().canonicalize().unwrap();
+        git(&repo, &["init", "-q", "-b", "main"]);
+        let conn = test_conn();
+        let file = repo.join("lib.rs");
+        let ts = Utc::now().format("%Y-%m-%dT%H:%M:%SZ").to_string();
+        let write = serde_json::json!({"file_path": file, "content": "fn a() {}\nfn b() {}\n"}).to_string();
+        db::insert_tool_use(&conn, "tu1", "s1", "Write", &ts, &ts, repo.to_str().unwrap(), &write).unwrap();
+        // A failed edit doesn't count
+        let edit = serde_json::json!({"file_path": file, "old_string": "x", "new_string": "fn c() {}"}).to_string();
+        db::insert_tool_use(&conn, "tu2", "s1", "Edit", &ts, &ts, repo.to_str().unwrap(), &edit).unwrap();
+        db::update_tool_use_outcome(&conn, "tu2", "error", None, Some("not found")).unwrap();
+
+        fs::write(&file, "fn a() {}\nfn b() {}\nfn c() {}\nfn mine() {}\n").unwrap();
+        git(&repo, &["add", "lib.rs"]);
+        git(&repo, &["commit", "-q", "-m", "Add lib"]);
+        post_commit(&conn, &repo, &Config::default()).unwrap();
+
+        let (agent, human, pct): (i64, i64, f64) = conn
+            .query_row("SELECT agent_lines, human_added, agent_percentage FROM commits", [], |r| {
+                Ok((r.get(0)?, r.get(1)?, r.get(2)?))
+            })
+            .unwrap();
+        assert_eq!((agent, human), (2, 2));
+        assert!((pct - 50.0).abs() < 0.01);
+    }
+}
@@ -1,7 +1,7 @@
 use std::fs;
 use std::path::{Path, PathBuf};
 
-/// The 6 hook events we register.
+/// The 9 hook events we register.
 pub const HOOK_EVENTS: &[&str] = &[
     "SessionStart",
     "SessionEnd",
@@ -9,8 +9,19 @@ pub const HOOK_EVENTS: &[&str] = &[
     "Stop",
     "PreToolUse",
     "PostToolUse",
+    "Notification",
+    "SubagentStop",
+    "PreCompact",
 ];
 
+/// The git hooks `install --git` adds to a repository.
+pub const GIT_HOOKS: &[&str] = &["prepare-commit-msg", "post-commit"];
+
+/// Markers around the claude-track lines in a git hook script, so they can
+/// coexist with the user's own hook code and be removed cleanly.
+pub const GIT_HOOK_BEGIN: &str = "# >>> claude-track >>>";
+pub const GIT_HOOK_END: &str = "# <<< claude-track <<<";
+
 /// The standard install directory for user-local binaries.
 pub fn install_dir() -> Result<PathBuf, Box<dyn std::error::Error>> {
     let home = dirs::home_dir().ok_or("could not determine home directory")?;
@@ -41,15 +52,29 @@ pub fn copy_binary(src: &Path, dest_dir: &Path) -> Result<PathBuf, Box<dyn std::
     Ok(dest)
 }
 
-/// Install all hooks into ~/.claude/settings.json.
+/// Install all hooks into ~/.claude/settings.json, or with `git`, the git
+/// hooks into the repository containing the current directory.
 #[cfg(not(tarpaulin_include))]
-pub fn run() {
-    if let Err(e) = try_run() {
+pub fn run(git: bool) {
+    let result = if git { try_run_git() } else { try_run() };
+    if let Err(e) = result {
         eprintln!("claude-track install: {e}");
         std::process::exit(1);
     }
 }
 
+#[cfg(not(tarpaulin_include))]
+fn try_run_git() -> Result<(), Box<dyn std::error::Error>> {
+    let cwd = std::env::current_dir()?;
+    let hooks_dir = crate::git::hooks_dir(&cwd).ok_or("not inside a git repository")?;
+    // Prefer the installed binary so the hooks survive rebuilds
+    let installed = install_dir()?.join("claude-track");
+    let binary = if installed.exists() { installed } else { std::env::current_exe()? };
+    let binary = binary.to_str().ok_or("binary path is not valid UTF-8")?;
+    print!("{}", install_git_hooks(&hooks_dir, &format!("{} git-hook", shell_quote(binary)))?);
+    Ok(())
+}
+
 #[cfg(not(tarpaulin_include))]
 fn try_run() -> Result<(), Box<dyn std::error::Error>> {
     let current_exe = std::env::current_exe()?;
@@ -94,7 +119,7 @@ fn try_run() -> Result<(), Box<dyn std::error::Error>> {
     Ok(())
 }
 
-/// Install all 6 hooks into the given settings file. Returns user-facing output.
+/// Install all 9 hooks into the given settings file. Returns user-facing output.
 pub fn install_to(
     settings_path: &Path,
     command: &str,
@@ -127,7 +152,7 @@ pub fn install_to(
     }
 }
 
-/// Add hook entries for all 6 events. Returns the number of hooks actually added.
+/// Add hook entries for all 9 events. Returns the number of hooks actually added.
 /// Removes stale claude-track hooks from other paths before adding.
 pub fn patch_settings(settings: &mut serde_json::Value, command: &str) -> usize {
     remove_stale_hooks(settings, command);
@@ -225,6 +250,72 @@ fn add_hook_entry(settings: &mut serde_json::Value, event: &str, command: &str)
     event_hooks.as_array_mut().unwrap().push(hook_entry);
 }
 
+/// Add the claude-track block to each of `GIT_HOOKS` in `hooks_dir`, calling
+/// `command <hook-name> "$@"`. Existing hook scripts keep their contents, with
+/// the block placed right after the shebang so an `exit` or `exec` in the
+/// user's script can't skip it; a block from an earlier install is replaced.
+/// Returns user-facing output.
+pub fn install_git_hooks(hooks_dir: &Path, command: &str) -> Result<String, Box<dyn std::error::Error>> {
+    fs::create_dir_all(hooks_dir)?;
+    let mut added = 0;
+    for name in GIT_HOOKS {
+        let path = hooks_dir.join(name);
+        let existing = fs::read_to_string(&path).unwrap_or_default();
+        let mut rest = strip_git_hook_block(&existing);
+        if rest.trim().is_empty() {
+            rest = "#!/bin/sh\n".to_string();
+        }
+        let (shebang, body) = match rest.strip_prefix("#!") {
+            Some(_) => rest.split_at(rest.find('\n').map_or(rest.len(), |i| i + 1)),
+            None => ("", rest.as_str()),
+        };
+        let mut script = shebang.to_string();
+        if !script.is_empty() && !script.ends_with('\n') {
+            script.push('\n');
+        }
+        script.push_str(&format!("{GIT_HOOK_BEGIN}\n{command} {name} \"$@\" || true\n{GIT_HOOK_END}\n"));
+        script.push_str(body);
+        if script != existing {
+            fs::write(&path, &script)?;
+            added += 1;
+        }
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            fs::set_permissions(&path, fs::Permissions::from_mode(0o755))?;
+        }
+    }
+
+    if added > 0 {
+        Ok(format!("Installed {added} git hook(s) in {}\n", hooks_dir.display()))
+    } else {
+        Ok("All git hooks are already installed.\n".to_string())
+    }
+}
+
+/// Remove the claude-track block from a hook script.
+pub fn strip_git_hook_block(script: &str) -> String {
+    let mut out = String::new();
+    let mut in_block = false;
+    for line in script.lines() {
+        match line.trim() {
+            GIT_HOOK_BEGIN => in_block = true,
+            GIT_HOOK_END => in_block = false,
+            _ if !in_block => {
+                out.push_str(line);
+                out.push('\n');
+            }
+            _ => {}
+        }
+    }
+    out
+}
+
+/// Quote `s` for a POSIX shell.
+fn shell_quote(s: &str) -> String {
+    format!("'{}'", s.replace('\'', "'\\''"))
+}
+
 /// Write settings to the given path, creating parent directories if needed.
 pub fn write_settings(
     settings: &serde_json::Value,
@@ -247,7 +338,7 @@ mod tests {
     fn patch_empty_settings() {
         let mut settings = serde_json::json!({});
         let added = patch_settings(&mut settings, "claude-track hook");
-        assert_eq!(added, 6);
+        assert_eq!(added, 9);
 
         for event in HOOK_EVENTS {
             let hooks = settings["hooks"][event].as_array().unwrap();
@@ -271,7 +362,7 @@ mod tests {
             }
         });
         let added = patch_settings(&mut settings, "claude-track hook");
-        assert_eq!(added, 6);
+        assert_eq!(added, 9);
 
         // PostToolUse should have 2 entries now
         let hooks = settings["hooks"]["PostToolUse"].as_array().unwrap();
@@ -305,7 +396,7 @@ mod tests {
             }
         });
         let added = patch_settings(&mut settings, "claude-track hook");
-        assert_eq!(added, 4); // 6 - 2 already installed
+        assert_eq!(added, 7); // 9 - 2 already installed
     }
 
     #[test]
@@ -340,7 +431,7 @@ mod tests {
         let settings_path = dir.path().join("settings.json");
 
         let output = install_to(&settings_path, "claude-track hook").unwrap();
-        assert!(output.contains("Registered 6 hook(s)"));
+        assert!(output.contains("Registered 9 hook(s)"));
         assert!(output.contains("Installed successfully."));
         assert!(output.contains("claude-track stats"));
 
@@ -382,7 +473,7 @@ mod tests {
         patch_settings(&mut settings, "/home/user/repos/proj/target/release/claude-track hook");
         // Now reinstall from ~/.local/bin path
         let added = patch_settings(&mut settings, "/home/user/.local/bin/claude-track hook");
-        assert_eq!(added, 6);
+        assert_eq!(added, 9);
         // Each event should have exactly 1 entry (old one removed, new one added)
         for event in HOOK_EVENTS {
             let hooks = settings["hooks"][event].as_array().unwrap();
@@ -470,6 +561,61 @@ mod tests {
         assert!(!is_hook_installed(&settings, "PostToolUse", "claude-track hook"));
     }
 
+    #[test]
+    fn install_git_hooks_fresh() {
+        let dir = TempDir::new().unwrap();
+        let output = install_git_hooks(dir.path(), "'/bin/claude-track' git-hook").unwrap();
+        assert!(output.contains("Installed 2 git hook(s)"));
+
+        let script = fs::read_to_string(dir.path().join("post-commit")).unwrap();
+        assert!(script.starts_with("#!/bin/sh\n"));
+        assert!(script.contains("'/bin/claude-track' git-hook post-commit \"$@\" || true"));
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let perms = fs::metadata(dir.path().join("post-commit")).unwrap().permissions();
+            assert_eq!(perms.mode() & 0o777, 0o755);
+        }
+
+        let output = install_git_hooks(dir.path(), "'/bin/claude-track' git-hook").unwrap();
+        assert!(output.contains("already installed"));
+    }
+
+    #[test]
+    fn install_git_hooks_keeps_user_hook_and_replaces_old_block() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("prepare-commit-msg");
+        fs::write(&path, "#!/bin/bash\necho mine").unwrap();
+        install_git_hooks(dir.path(), "/old/claude-track git-hook").unwrap();
+        install_git_hooks(dir.path(), "/new/claude-track git-hook").unwrap();
+
+        let script = fs::read_to_string(&path).unwrap();
+        assert!(script.starts_with(&format!("#!/bin/bash\n{GIT_HOOK_BEGIN}\n")));
+        assert!(script.ends_with(&format!("{GIT_HOOK_END}\necho mine\n")));
+        assert!(!script.contains("/old/"));
+        assert_eq!(script.matches(GIT_HOOK_BEGIN).count(), 1);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn install_git_hooks_runs_before_user_hook_exits() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("post-commit");
+        fs::write(&path, "#!/bin/sh\necho mine\nexit 0\n").unwrap();
+        let marker = dir.path().join("ran");
+        install_git_hooks(dir.path(), &format!("touch {} ||", shell_quote(marker.to_str().unwrap()))).unwrap();
+
+        let output = std::process::Command::new(&path).output().unwrap();
+        assert!(output.status.success());
+        assert_eq!(String::from_utf8_lossy(&output.stdout), "mine\n");
+        assert!(marker.exists());
+    }
+
+    #[test]
+    fn shell_quote_escapes_single_quotes() {
+        assert_eq!(shell_quote("/it's/here"), "'/it'\\''s/here'");
+    }
+
     #[test]
     fn install_dir_returns_local_bin() {
         let dir = install_dir().unwrap();
@@ -193,6 +193,7 @@ mod tests {
             }
             fn consume(&mut self, _amt: usize) {}
 
+            #[allow(clippy::io_other_error)]
             fn read_line(&mut self, buf: &mut String) -> std::io::Result<usize> {
                 self.calls += 1;
                 match self.calls {
@@ -1,7 +1,12 @@
 pub mod backfill;
+pub mod checkpoint;
+pub mod git_hook;
 pub mod hook;
 pub mod install;
 pub mod migrate;
 pub mod query;
+pub mod redact;
+pub mod replay;
+pub mod show;
 pub mod stats;
 pub mod uninstall;
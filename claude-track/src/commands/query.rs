@@ -68,8 +68,12 @@ pub fn execute_query_on(
     Ok(out)
 }
 
+/// Format a column value for output. zstd-compressed blobs (stored tool
+/// responses) are decompressed so they read like any other text column.
 fn format_value(v: &rusqlite::types::Value) -> String {
     match v {
+        rusqlite::types::Value::Blob(b) if db::is_zstd(b) => db::decompress_text(b)
+            .unwrap_or_else(|_| format!("<blob {} bytes>", b.len())),
         rusqlite::types::Value::Null => "NULL".to_string(),
         rusqlite::types::Value::Integer(i) => i.to_string(),
         rusqlite::types::Value::Real(f) => f.to_string(),
@@ -162,6 +166,7 @@ mod tests {
     }
 
     #[test]
+    #[allow(clippy::approx_constant)]
     fn format_value_types() {
         assert_eq!(format_value(&rusqlite::types::Value::Null), "NULL");
         assert_eq!(format_value(&rusqlite::types::Value::Integer(42)), "42");
@@ -175,4 +180,14 @@ mod tests {
             "<blob 3 bytes>"
         );
     }
+
+    #[test]
+    fn query_decompresses_stored_responses() {
+        let conn = test_conn();
+        db::insert_tool_response(&conn, "tu1", "compressed output", 17, false, true).unwrap();
+        db::insert_tool_response(&conn, "tu2", "plain output", 12, false, false).unwrap();
+        let output = execute_query(&conn, "SELECT body FROM tool_responses ORDER BY tool_use_id").unwrap();
+        let lines: Vec<&str> = output.lines().collect();
+        assert_eq!(lines, vec!["body", "compressed output", "plain output"]);
+    }
 }
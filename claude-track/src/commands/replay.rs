@@ -0,0 +1,420 @@
+use std::fs;
+use std::io::Cursor;
+use std::path::Path;
+
+use chrono::{DateTime, Utc};
+use rusqlite::{params, Connection};
+
+use crate::commands::hook;
+use crate::config::{self, Config};
+use crate::db;
+use crate::redact::Redactor;
+
+/// Rebuild a database by replaying the raw event log.
+#[cfg(not(tarpaulin_include))]
+pub fn run(since: Option<&str>, session: Option<&str>, into: Option<&Path>) {
+    if let Err(e) = try_run(since, session, into) {
+        eprintln!("claude-track replay: {e}");
+        std::process::exit(1);
+    }
+}
+
+#[cfg(not(tarpaulin_include))]
+fn try_run(
+    since: Option<&str>,
+    session: Option<&str>,
+    into: Option<&Path>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let db_path = db::db_path()?;
+    let config = config::load()?;
+    let output = replay_from(&db_path, since, session, into, &config)?;
+    print!("{output}");
+    Ok(())
+}
+
+/// Replay events from the database at `db_path`.
+///
+/// With `into`, events are written to a new database at that path and the
+/// source is left untouched. Without it, the source is rebuilt in place: the
+/// replay goes to a scratch file which then replaces the original, and the
+/// original is kept as `claude-track.db.bak`. Filters require `into` so an
+/// in-place rebuild can never drop history, and rows that no event produces
+/// are carried over (see `carry_over`). Events are handled under the current
+/// `config`, so e.g. a newly enabled response policy applies to old events.
+pub fn replay_from(
+    db_path: &Path,
+    since: Option<&str>,
+    session: Option<&str>,
+    into: Option<&Path>,
+    config: &Config,
+) -> Result<String, Box<dyn std::error::Error>> {
+    if !db_path.exists() {
+        return Err(format!("no tracking database at {}", db_path.display()).into());
+    }
+    if into.is_none() && (since.is_some() || session.is_some()) {
+        return Err("--since and --session require --into (an in-place rebuild must keep every event)".into());
+    }
+
+    let scratch = db_path.with_file_name("claude-track.replay.db");
+    let target_path = into.unwrap_or(&scratch);
+    if into.is_some() && target_path.exists() {
+        return Err(format!("refusing to overwrite existing file {}", target_path.display()).into());
+    }
+    if into.is_none() {
+        remove_db_files(&scratch)?;
+    }
+
+    let source = db::open_db(db_path)?;
+    let target = db::open_db(target_path)?;
+    if into.is_none() {
+        // Before replaying, so hashed captures use the same salt as before
+        copy_rows(&target, db_path, "settings", "1")?;
+    }
+    let (replayed, failed) = replay_events(&source, &target, since, session, config)?;
+    if into.is_none() {
+        carry_over(&target, db_path)?;
+    }
+    drop(source);
+    drop(target);
+
+    let mut output = format!("Replayed {replayed} events");
+    if failed > 0 {
+        output.push_str(&format!(" ({failed} failed)"));
+    }
+
+    match into {
+        Some(path) => output.push_str(&format!(" into {}\n", path.display())),
+        None => {
+            let backup = db_path.with_file_name("claude-track.db.bak");
+            remove_db_files(&backup)?;
+            fs::rename(db_path, &backup)?;
+            for suffix in ["-wal", "-shm"] {
+                let _ = fs::remove_file(sidecar(db_path, suffix));
+            }
+            fs::rename(&scratch, db_path)?;
+            output.push_str(&format!(
+                " into {}\nPrevious database kept at {}\n",
+                db_path.display(),
+                backup.display()
+            ));
+        }
+    }
+    Ok(output)
+}
+
+/// Feed events from `source` through `hook::dispatch_at` into `target`, in
+/// sequence order. Handlers that read the transcript see the file as it is
+/// now, not as it was when the event first fired.
+/// Returns `(replayed, failed)` counts.
+pub fn replay_events(
+    source: &Connection,
+    target: &Connection,
+    since: Option<&str>,
+    session: Option<&str>,
+    config: &Config,
+) -> Result<(u64, u64), Box<dyn std::error::Error>> {
+    let mut stmt = source.prepare(
+        "SELECT received_at, raw_json FROM events
+         WHERE (?1 IS NULL OR received_at >= ?1)
+           AND (?2 IS NULL OR session_id = ?2)
+         ORDER BY seq",
+    )?;
+    let rows = stmt.query_map(params![since, session], |r| {
+        Ok((r.get::<_, String>(0)?, r.get::<_, String>(1)?))
+    })?;
+
+    let redactor = Redactor::new(&config.redaction)?;
+    let tx = target.unchecked_transaction()?;
+    let mut replayed = 0u64;
+    let mut failed = 0u64;
+    for row in rows {
+        let (received_at, raw) = row?;
+        let received_at = DateTime::parse_from_rfc3339(&received_at)?.with_timezone(&Utc);
+        match hook::dispatch_at(Cursor::new(raw), received_at, config, &redactor, &tx) {
+            Ok(()) => replayed += 1,
+            Err(_) => failed += 1,
+        }
+    }
+    tx.commit()?;
+
+    Ok((replayed, failed))
+}
+
+/// Copy rows that replaying events can't recreate from the database at
+/// `source_path` into `target`: tool calls imported by `migrate`, plans found
+/// by `backfill`, archived transcript chunks (whose transcripts may be gone)
+/// and commits recorded by the git hook.
+fn carry_over(target: &Connection, source_path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+    copy_rows(target, source_path, "tool_uses", "tool_use_id IS NULL")?;
+    copy_rows(
+        target,
+        source_path,
+        "plans",
+        "tool_use_id NOT IN (SELECT tool_use_id FROM main.plans WHERE tool_use_id IS NOT NULL)",
+    )?;
+    copy_rows(target, source_path, "transcript_chunks", "1")?;
+    copy_rows(target, source_path, "commits", "1")?;
+    copy_rows(target, source_path, "commit_files", "1")?;
+    Ok(())
+}
+
+/// Insert the rows of `table` matching `filter` from the database at
+/// `source_path` into the same table in `target`, skipping rows whose key
+/// already exists. An autoincrement `id` is left for the target to assign.
+fn copy_rows(
+    target: &Connection,
+    source_path: &Path,
+    table: &str,
+    filter: &str,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let columns: Vec<String> = target
+        .prepare(&format!("SELECT name FROM pragma_table_info('{table}') WHERE name != 'id'"))?
+        .query_map([], |r| r.get(0))?
+        .collect::<Result<_, _>>()?;
+    let columns = columns.join(", ");
+    target.execute("ATTACH DATABASE ?1 AS source", params![source_path.to_string_lossy()])?;
+    let copied = target.execute_batch(&format!(
+        "INSERT OR IGNORE INTO main.{table} ({columns}) SELECT {columns} FROM source.{table} WHERE {filter}"
+    ));
+    target.execute_batch("DETACH DATABASE source")?;
+    copied?;
+    Ok(())
+}
+
+/// Path of a SQLite sidecar file such as `<db>-wal`.
+fn sidecar(db_path: &Path, suffix: &str) -> std::path::PathBuf {
+    let mut name = db_path.as_os_str().to_owned();
+    name.push(suffix);
+    name.into()
+}
+
+/// Remove a database file and its WAL/SHM sidecars if present.
+fn remove_db_files(path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+    if path.exists() {
+        fs::remove_file(path)?;
+    }
+    for suffix in ["-wal", "-shm"] {
+        let _ = fs::remove_file(sidecar(path, suffix));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn test_conn() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        db::init_db(&conn).unwrap();
+        conn
+    }
+
+    fn log_event(conn: &Connection, received_at: &str, session_id: &str, raw: &str) {
+        let event: serde_json::Value = serde_json::from_str(raw).unwrap();
+        db::insert_event(
+            conn,
+            received_at,
+            event["hook_event_name"].as_str().unwrap_or("PostToolUse"),
+            session_id,
+            raw,
+        )
+        .unwrap();
+    }
+
+    fn seed(conn: &Connection) {
+        log_event(
+            conn,
+            "2026-02-01T10:00:00.000Z",
+            "s1",
+            r#"{"hook_event_name":"SessionStart","session_id":"s1","cwd":"/a","reason":"startup"}"#,
+        );
+        log_event(
+            conn,
+            "2026-02-01T10:00:05.250Z",
+            "s1",
+            r#"{"hook_event_name":"UserPromptSubmit","session_id":"s1","prompt":"first"}"#,
+        );
+        log_event(
+            conn,
+            "2026-03-01T09:00:00.000Z",
+            "s2",
+            r#"{"hook_event_name":"UserPromptSubmit","session_id":"s2","prompt":"second"}"#,
+        );
+    }
+
+    #[test]
+    fn replay_rebuilds_derived_tables_with_original_timestamps() {
+        let source = test_conn();
+        seed(&source);
+        let target = test_conn();
+
+        let (replayed, failed) = replay_events(&source, &target, None, None, &Config::default()).unwrap();
+        assert_eq!((replayed, failed), (3, 0));
+
+        let started: String = target
+            .query_row("SELECT started_at FROM sessions WHERE session_id='s1'", [], |r| r.get(0))
+            .unwrap();
+        assert_eq!(started, "2026-02-01T10:00:00Z");
+
+        let prompts: Vec<(String, String)> = target
+            .prepare("SELECT timestamp, prompt_text FROM prompts ORDER BY id")
+            .unwrap()
+            .query_map([], |r| Ok((r.get(0)?, r.get(1)?)))
+            .unwrap()
+            .filter_map(|r| r.ok())
+            .collect();
+        assert_eq!(
+            prompts,
+            vec![
+                ("2026-02-01T10:00:05Z".to_string(), "first".to_string()),
+                ("2026-03-01T09:00:00Z".to_string(), "second".to_string()),
+            ]
+        );
+
+        // The target gets its own copy of the raw log, with original receive times
+        let received: Vec<String> = target
+            .prepare("SELECT received_at FROM events ORDER BY seq")
+            .unwrap()
+            .query_map([], |r| r.get(0))
+            .unwrap()
+            .filter_map(|r| r.ok())
+            .collect();
+        assert_eq!(
+            received,
+            vec!["2026-02-01T10:00:00.000Z", "2026-02-01T10:00:05.250Z", "2026-03-01T09:00:00.000Z"]
+        );
+    }
+
+    #[test]
+    fn replay_filters_by_since_and_session() {
+        let source = test_conn();
+        seed(&source);
+
+        let target = test_conn();
+        let (replayed, _) = replay_events(&source, &target, Some("2026-02-15"), None, &Config::default()).unwrap();
+        assert_eq!(replayed, 1);
+
+        let target = test_conn();
+        let (replayed, _) = replay_events(&source, &target, None, Some("s1"), &Config::default()).unwrap();
+        assert_eq!(replayed, 2);
+        let sessions: i64 = target
+            .query_row("SELECT COUNT(DISTINCT session_id) FROM events", [], |r| r.get(0))
+            .unwrap();
+        assert_eq!(sessions, 1);
+    }
+
+    #[test]
+    fn replay_counts_failures_and_continues() {
+        let source = test_conn();
+        db::insert_event(&source, "2026-02-01T10:00:00.000Z", "Stop", "s1", "not json").unwrap();
+        log_event(
+            &source,
+            "2026-02-01T10:00:01.000Z",
+            "s1",
+            r#"{"hook_event_name":"UserPromptSubmit","session_id":"s1","prompt":"ok"}"#,
+        );
+        let target = test_conn();
+
+        let (replayed, failed) = replay_events(&source, &target, None, None, &Config::default()).unwrap();
+        assert_eq!((replayed, failed), (1, 1));
+    }
+
+    #[test]
+    fn replay_from_into_new_file() {
+        let dir = TempDir::new().unwrap();
+        let db_path = dir.path().join("claude-track.db");
+        seed(&db::open_db(&db_path).unwrap());
+        let into = dir.path().join("copy.db");
+
+        let output = replay_from(&db_path, None, Some("s2"), Some(&into), &Config::default()).unwrap();
+        assert!(output.contains("Replayed 1 events into"));
+
+        let copy = db::open_db(&into).unwrap();
+        let prompt: String = copy
+            .query_row("SELECT prompt_text FROM prompts", [], |r| r.get(0))
+            .unwrap();
+        assert_eq!(prompt, "second");
+    }
+
+    #[test]
+    fn replay_from_refuses_existing_target() {
+        let dir = TempDir::new().unwrap();
+        let db_path = dir.path().join("claude-track.db");
+        db::open_db(&db_path).unwrap();
+        let into = dir.path().join("exists.db");
+        fs::write(&into, "").unwrap();
+
+        let err = replay_from(&db_path, None, None, Some(&into), &Config::default()).unwrap_err();
+        assert!(err.to_string().contains("refusing to overwrite"));
+    }
+
+    #[test]
+    fn replay_from_filters_require_into() {
+        let dir = TempDir::new().unwrap();
+        let db_path = dir.path().join("claude-track.db");
+        db::open_db(&db_path).unwrap();
+
+        let err = replay_from(&db_path, Some("2026-01-01"), None, None, &Config::default()).unwrap_err();
+        assert!(err.to_string().contains("require --into"));
+    }
+
+    #[test]
+    fn replay_from_missing_db() {
+        let dir = TempDir::new().unwrap();
+        let err = replay_from(&dir.path().join("nope.db"), None, None, None, &Config::default()).unwrap_err();
+        assert!(err.to_string().contains("no tracking database"));
+    }
+
+    #[test]
+    fn replay_from_in_place_keeps_backup() {
+        let dir = TempDir::new().unwrap();
+        let db_path = dir.path().join("claude-track.db");
+        let conn = db::open_db(&db_path).unwrap();
+        seed(&conn);
+        let salt = db::capture_salt(&conn).unwrap();
+        // Rows no event produces: a migrated call, a backfilled plan, an
+        // archived chunk and a commit from the git hook
+        db::insert_migrated_tool_use(&conn, "s0", "Read", "ts", "/a", "{}").unwrap();
+        db::insert_plan(&conn, "s0", "toolu_plan", "ts", "plan").unwrap();
+        db::insert_transcript_chunk(&conn, "s0", 0, 10, "abc").unwrap();
+        conn.execute("INSERT INTO commits (sha, repo) VALUES ('c1', '/a')", []).unwrap();
+        conn.execute("INSERT INTO commit_files (commit_sha, path) VALUES ('c1', 'x.rs')", []).unwrap();
+        drop(conn);
+
+        let output = replay_from(&db_path, None, None, None, &Config::default()).unwrap();
+        assert!(output.contains("Replayed 3 events"));
+        assert!(output.contains("claude-track.db.bak"));
+        assert!(dir.path().join("claude-track.db.bak").exists());
+        assert!(!dir.path().join("claude-track.replay.db").exists());
+
+        let rebuilt = db::open_db(&db_path).unwrap();
+        let count = |sql: &str| -> i64 { rebuilt.query_row(sql, [], |r| r.get(0)).unwrap() };
+        assert_eq!(count("SELECT COUNT(*) FROM prompts"), 2);
+        assert_eq!(count("SELECT COUNT(*) FROM tool_uses WHERE tool_use_id IS NULL AND session_id = 's0'"), 1);
+        assert_eq!(count("SELECT COUNT(*) FROM plans WHERE tool_use_id = 'toolu_plan'"), 1);
+        assert_eq!(count("SELECT COUNT(*) FROM transcript_chunks WHERE sha256 = 'abc'"), 1);
+        assert_eq!(count("SELECT COUNT(*) FROM commits"), 1);
+        assert_eq!(count("SELECT COUNT(*) FROM commit_files"), 1);
+        assert_eq!(db::capture_salt(&rebuilt).unwrap(), salt);
+    }
+
+    #[test]
+    fn replay_from_in_place_twice_does_not_duplicate() {
+        let dir = TempDir::new().unwrap();
+        let db_path = dir.path().join("claude-track.db");
+        let conn = db::open_db(&db_path).unwrap();
+        seed(&conn);
+        db::insert_migrated_tool_use(&conn, "s0", "Read", "ts", "/a", "{}").unwrap();
+        db::insert_plan(&conn, "s0", "toolu_plan", "ts", "plan").unwrap();
+        drop(conn);
+
+        replay_from(&db_path, None, None, None, &Config::default()).unwrap();
+        replay_from(&db_path, None, None, None, &Config::default()).unwrap();
+
+        let rebuilt = db::open_db(&db_path).unwrap();
+        let count = |sql: &str| -> i64 { rebuilt.query_row(sql, [], |r| r.get(0)).unwrap() };
+        assert_eq!(count("SELECT COUNT(*) FROM tool_uses"), 1);
+        assert_eq!(count("SELECT COUNT(*) FROM plans"), 1);
+    }
+}
@@ -0,0 +1,371 @@
+use std::fmt;
+
+use rusqlite::{params, Connection};
+
+use crate::commands::stats::{format_duration_ms, summarize_tool_input};
+use crate::db;
+
+/// Print the timeline of a single session, or with `chain`, of every session
+/// in its conversation.
+#[cfg(not(tarpaulin_include))]
+pub fn run(session: &str, full: bool, chain: bool) {
+    if let Err(e) = try_run(session, full, chain) {
+        eprintln!("claude-track show: {e}");
+        std::process::exit(1);
+    }
+}
+
+#[cfg(not(tarpaulin_include))]
+fn try_run(session: &str, full: bool, chain: bool) -> Result<(), Box<dyn std::error::Error>> {
+    let db_path = db::db_path()?;
+    let conn = db::open_db(&db_path)?;
+    if chain {
+        print!("{}", show_chain(&conn, session, full)?);
+    } else {
+        print!("{}", show_session(&conn, session, full)?);
+    }
+    Ok(())
+}
+
+/// One entry in a session timeline.
+enum Entry {
+    Prompt {
+        text: String,
+    },
+    /// The final assistant message of a turn.
+    Answer {
+        text: String,
+        truncated: bool,
+    },
+    Tool {
+        tool_use_id: String,
+        tool_name: String,
+        input: String,
+        status: Option<String>,
+        duration_ms: Option<i64>,
+        summary: String,
+    },
+}
+
+/// Render the session whose id is `session` (or starts with it) as prompts
+/// interleaved with the tool calls they led to, each turn ending with the
+/// agent's answer. With `full`, each tool call shows its stored full response
+/// (see the `responses` config) instead of the short summary.
+pub fn show_session(
+    conn: &Connection,
+    session: &str,
+    full: bool,
+) -> Result<String, Box<dyn std::error::Error>> {
+    let session_id = resolve_session(conn, session)?;
+    let mut out = String::new();
+
+    fmt::write(&mut out, format_args!("Session {session_id}\n")).unwrap();
+    let header = conn
+        .query_row(
+            "SELECT cwd, started_at, ended_at, end_reason FROM sessions WHERE session_id = ?1",
+            params![session_id],
+            |r| {
+                Ok((
+                    r.get::<_, Option<String>>(0)?,
+                    r.get::<_, Option<String>>(1)?,
+                    r.get::<_, Option<String>>(2)?,
+                    r.get::<_, Option<String>>(3)?,
+                ))
+            },
+        )
+        .ok();
+    if let Some((cwd, started, ended, reason)) = header {
+        if let Some(cwd) = cwd.filter(|c| !c.is_empty()) {
+            fmt::write(&mut out, format_args!("  Project: {cwd}\n")).unwrap();
+        }
+        if let Some(started) = started {
+            fmt::write(&mut out, format_args!("  Started: {started}\n")).unwrap();
+        }
+        if let Some(ended) = ended {
+            match reason.filter(|r| !r.is_empty()) {
+                Some(reason) => fmt::write(&mut out, format_args!("  Ended:   {ended} ({reason})\n")).unwrap(),
+                None => fmt::write(&mut out, format_args!("  Ended:   {ended}\n")).unwrap(),
+            }
+        }
+    }
+    let mut stmt = conn.prepare(
+        "SELECT 'Continues:   ', previous_session_id, reason FROM session_links WHERE session_id = ?1
+         UNION ALL
+         SELECT 'Continued by:', session_id, reason FROM session_links WHERE previous_session_id = ?1
+         ORDER BY 1 DESC, 2",
+    )?;
+    let links = stmt.query_map(params![session_id], |r| {
+        Ok((r.get::<_, String>(0)?, r.get::<_, String>(1)?, r.get::<_, Option<String>>(2)?))
+    })?;
+    for link in links {
+        let (label, other, reason) = link?;
+        fmt::write(&mut out, format_args!("  {label} {other} ({})\n", reason.unwrap_or_default())).unwrap();
+    }
+    out.push('\n');
+
+    for (timestamp, entry) in timeline(conn, &session_id)? {
+        let time = timestamp.get(11..19).unwrap_or(&timestamp);
+        match entry {
+            Entry::Prompt { text } => {
+                fmt::write(&mut out, format_args!("[{time}] > {}\n", first_line(&text))).unwrap();
+                for line in text.lines().skip(1) {
+                    fmt::write(&mut out, format_args!("           {line}\n")).unwrap();
+                }
+            }
+            Entry::Answer { text, truncated } => {
+                fmt::write(&mut out, format_args!("[{time}] < {}\n", first_line(&text))).unwrap();
+                for line in text.lines().skip(1) {
+                    fmt::write(&mut out, format_args!("           {line}\n")).unwrap();
+                }
+                if truncated {
+                    out.push_str("           [truncated by answer size cap]\n");
+                }
+            }
+            Entry::Tool { tool_use_id, tool_name, input, status, duration_ms, summary } => {
+                let mut meta = Vec::new();
+                if let Some(status) = status.filter(|s| s != "ok") {
+                    meta.push(status);
+                }
+                if let Some(ms) = duration_ms {
+                    meta.push(format_duration_ms(ms));
+                }
+                let meta = if meta.is_empty() { String::new() } else { format!(" ({})", meta.join(", ")) };
+                fmt::write(
+                    &mut out,
+                    format_args!("[{time}]   {tool_name}{meta} {}\n", summarize_tool_input(&tool_name, &input, 80)),
+                )
+                .unwrap();
+
+                let stored = if full { db::get_tool_response(conn, &tool_use_id)? } else { None };
+                let (body, truncated) = stored.unwrap_or((summary, false));
+                for line in body.lines() {
+                    fmt::write(&mut out, format_args!("             | {line}\n")).unwrap();
+                }
+                if truncated {
+                    out.push_str("             | [truncated by response size cap]\n");
+                }
+            }
+        }
+    }
+
+    Ok(out)
+}
+
+/// Render every session in the conversation `session` belongs to, oldest
+/// first. See `db::get_session_chain`.
+pub fn show_chain(conn: &Connection, session: &str, full: bool) -> Result<String, Box<dyn std::error::Error>> {
+    let session_id = resolve_session(conn, session)?;
+    let chain = db::get_session_chain(conn, &session_id)?;
+    if chain.len() == 1 {
+        return show_session(conn, &session_id, full);
+    }
+    let mut out = String::new();
+    fmt::write(&mut out, format_args!("Conversation of {} sessions\n\n", chain.len())).unwrap();
+    let sessions: Vec<String> = chain
+        .iter()
+        .map(|id| show_session(conn, id, full))
+        .collect::<Result<_, _>>()?;
+    out.push_str(&sessions.join("\n"));
+    Ok(out)
+}
+
+/// Find the one session id equal to or starting with `prefix`.
+fn resolve_session(conn: &Connection, prefix: &str) -> Result<String, Box<dyn std::error::Error>> {
+    let mut stmt = conn.prepare(
+        "SELECT session_id FROM sessions WHERE substr(session_id, 1, length(?1)) = ?1
+         UNION
+         SELECT session_id FROM tool_uses WHERE substr(session_id, 1, length(?1)) = ?1
+         UNION
+         SELECT session_id FROM prompts WHERE substr(session_id, 1, length(?1)) = ?1",
+    )?;
+    let ids: Vec<String> = stmt
+        .query_map(params![prefix], |r| r.get(0))?
+        .filter_map(|r| r.ok())
+        .collect();
+    if ids.iter().any(|id| id == prefix) {
+        return Ok(prefix.to_string());
+    }
+    match ids.len() {
+        0 => Err(format!("no session matching '{prefix}'").into()),
+        1 => Ok(ids.into_iter().next().unwrap()),
+        n => Err(format!("'{prefix}' matches {n} sessions; use a longer prefix").into()),
+    }
+}
+
+/// Prompts, tool calls and answers for a session in time order. A prompt
+/// sorts before tool calls with the same timestamp since it caused them, and
+/// an answer after them since it ends the turn.
+fn timeline(conn: &Connection, session_id: &str) -> Result<Vec<(String, Entry)>, Box<dyn std::error::Error>> {
+    let mut entries: Vec<(String, u8, i64, Entry)> = Vec::new();
+
+    let mut stmt = conn.prepare(
+        "SELECT id, COALESCE(timestamp, ''), COALESCE(prompt_text, '') FROM prompts WHERE session_id = ?1",
+    )?;
+    let rows = stmt.query_map(params![session_id], |r| {
+        Ok((r.get::<_, i64>(0)?, r.get::<_, String>(1)?, r.get::<_, String>(2)?))
+    })?;
+    for row in rows {
+        let (id, ts, text) = row?;
+        entries.push((ts, 0, id, Entry::Prompt { text }));
+    }
+
+    let mut stmt = conn.prepare(
+        "SELECT rowid, COALESCE(timestamp, ''), COALESCE(tool_use_id, ''), COALESCE(tool_name, ''),
+                COALESCE(input, ''), status, duration_ms, COALESCE(response_summary, '')
+         FROM tool_uses WHERE session_id = ?1",
+    )?;
+    let rows = stmt.query_map(params![session_id], |r| {
+        Ok((
+            r.get::<_, i64>(0)?,
+            r.get::<_, String>(1)?,
+            Entry::Tool {
+                tool_use_id: r.get(2)?,
+                tool_name: r.get(3)?,
+                input: r.get(4)?,
+                status: r.get(5)?,
+                duration_ms: r.get(6)?,
+                summary: r.get(7)?,
+            },
+        ))
+    })?;
+    for row in rows {
+        let (rowid, ts, entry) = row?;
+        entries.push((ts, 1, rowid, entry));
+    }
+
+    let mut stmt = conn.prepare(
+        "SELECT id, COALESCE(ended_at, started_at, ''), answer, COALESCE(answer_truncated, 0) FROM turns
+         WHERE session_id = ?1 AND answer IS NOT NULL AND answer != ''",
+    )?;
+    let rows = stmt.query_map(params![session_id], |r| {
+        Ok((
+            r.get::<_, i64>(0)?,
+            r.get::<_, String>(1)?,
+            Entry::Answer {
+                text: r.get(2)?,
+                truncated: r.get(3)?,
+            },
+        ))
+    })?;
+    for row in rows {
+        let (id, ts, entry) = row?;
+        entries.push((ts, 2, id, entry));
+    }
+
+    entries.sort_by(|a, b| (&a.0, a.1, a.2).cmp(&(&b.0, b.1, b.2)));
+    Ok(entries.into_iter().map(|(ts, _, _, entry)| (ts, entry)).collect())
+}
+
+fn first_line(text: &str) -> &str {
+    text.lines().next().unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_conn() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        db::init_db(&conn).unwrap();
+        conn
+    }
+
+    fn seed(conn: &Connection) {
+        db::insert_session_start(conn, "abc123", "2026-02-27T10:00:00Z", "startup", "/proj", "/t").unwrap();
+        db::update_session_end(conn, "abc123", "2026-02-27T11:00:00Z", "logout").unwrap();
+        db::insert_prompt(conn, "abc123", "2026-02-27T10:00:05Z", "fix the build\nit fails on CI").unwrap();
+        db::insert_tool_use(conn, "tu1", "abc123", "Bash", "2026-02-27T10:00:05Z", "2026-02-27T10:00:05.000Z", "/proj", r#"{"command":"cargo build"}"#).unwrap();
+        db::update_tool_use_response(conn, "tu1", "abc123", "Bash", "2026-02-27T10:00:07Z", "2026-02-27T10:00:07.500Z", "/proj", r#"{"command":"cargo build"}"#, "error[E0425]...").unwrap();
+        db::update_tool_use_outcome(conn, "tu1", "error", Some(101), Some("error[E0425]")).unwrap();
+    }
+
+    #[test]
+    fn show_session_timeline() {
+        let conn = test_conn();
+        seed(&conn);
+        let out = show_session(&conn, "abc123", false).unwrap();
+        assert!(out.contains("Session abc123"));
+        assert!(out.contains("Project: /proj"));
+        assert!(out.contains("Ended:   2026-02-27T11:00:00Z (logout)"));
+        assert!(out.contains("[10:00:05] > fix the build"));
+        assert!(out.contains("it fails on CI"));
+        assert!(out.contains("Bash (error, 2.5s) cargo build"));
+        assert!(out.contains("| error[E0425]..."));
+        // Prompt comes before the tool call it caused
+        assert!(out.find("fix the build").unwrap() < out.find("cargo build").unwrap());
+    }
+
+    #[test]
+    fn show_session_renders_answer_after_tool_calls() {
+        let conn = test_conn();
+        seed(&conn);
+        let prompt_id: i64 = conn.query_row("SELECT id FROM prompts", [], |r| r.get(0)).unwrap();
+        db::open_turn(&conn, "abc123", prompt_id, "2026-02-27T10:00:05Z", 0).unwrap();
+        db::close_turn(&conn, "abc123", "2026-02-27T10:00:09Z", Some(100)).unwrap();
+        db::set_turn_answer(&conn, "abc123", "Fixed the import.\nCI passes now.", Some(31), true, false).unwrap();
+
+        let out = show_session(&conn, "abc123", false).unwrap();
+        assert!(out.contains("[10:00:09] < Fixed the import.\n           CI passes now.\n"));
+        assert!(out.contains("[truncated by answer size cap]"));
+        assert!(out.find("cargo build").unwrap() < out.find("Fixed the import.").unwrap());
+    }
+
+    #[test]
+    fn show_session_full_uses_stored_response() {
+        let conn = test_conn();
+        seed(&conn);
+        db::insert_tool_response(&conn, "tu1", "line one\nline two", 30, true, true).unwrap();
+
+        let out = show_session(&conn, "abc123", true).unwrap();
+        assert!(out.contains("| line one\n"));
+        assert!(out.contains("| line two\n"));
+        assert!(out.contains("[truncated by response size cap]"));
+        assert!(!out.contains("error[E0425]..."));
+    }
+
+    #[test]
+    fn show_session_full_falls_back_to_summary() {
+        let conn = test_conn();
+        seed(&conn);
+        let out = show_session(&conn, "abc123", true).unwrap();
+        assert!(out.contains("| error[E0425]..."));
+    }
+
+    #[test]
+    fn show_chain_renders_linked_sessions_in_order() {
+        let conn = test_conn();
+        seed(&conn);
+        db::insert_session_start(&conn, "def456", "2026-02-27T11:00:01Z", "clear", "/proj", "/t2").unwrap();
+        db::insert_prompt(&conn, "def456", "2026-02-27T11:00:05Z", "now add tests").unwrap();
+        db::link_sessions(&conn, "def456", "abc123", "clear", "2026-02-27T11:00:01Z").unwrap();
+
+        let single = show_session(&conn, "def456", false).unwrap();
+        assert!(single.contains("  Continues:    abc123 (clear)\n"));
+        assert!(!single.contains("fix the build"));
+        assert!(show_session(&conn, "abc123", false).unwrap().contains("  Continued by: def456 (clear)\n"));
+
+        // Either end of the chain shows the whole conversation, oldest first
+        let out = show_chain(&conn, "def", false).unwrap();
+        assert!(out.starts_with("Conversation of 2 sessions\n"));
+        assert!(out.find("Session abc123").unwrap() < out.find("Session def456").unwrap());
+        assert!(out.find("fix the build").unwrap() < out.find("now add tests").unwrap());
+        assert_eq!(show_chain(&conn, "abc123", false).unwrap(), out);
+
+        // A session without links shows as itself
+        db::insert_prompt(&conn, "zzz", "2026-02-27T12:00:00Z", "alone").unwrap();
+        assert_eq!(show_chain(&conn, "zzz", false).unwrap(), show_session(&conn, "zzz", false).unwrap());
+    }
+
+    #[test]
+    fn show_session_prefix_match() {
+        let conn = test_conn();
+        seed(&conn);
+        db::insert_prompt(&conn, "abd999", "2026-02-27T12:00:00Z", "other").unwrap();
+
+        assert!(show_session(&conn, "abc", false).unwrap().contains("Session abc123"));
+        let err = show_session(&conn, "ab", false).unwrap_err();
+        assert!(err.to_string().contains("matches 2 sessions"));
+        let err = show_session(&conn, "zzz", false).unwrap_err();
+        assert!(err.to_string().contains("no session matching"));
+    }
+}
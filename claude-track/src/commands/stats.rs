@@ -1,9 +1,10 @@
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, BTreeSet};
 use std::fmt;
 use std::path::Path;
 
 use rusqlite::Connection;
 
+use crate::context;
 use crate::db;
 
 /// Print usage statistics from the SQLite database.
@@ -57,21 +58,57 @@ pub fn format_report(conn: &Connection, file_size: u64, db_path: &Path) -> Strin
     // --- Token Usage ---
     out.push_str(&format_tokens_section(conn));
 
+    // --- Context Window ---
+    out.push_str(&format_context_section(conn));
+
+    // --- Cost by Branch ---
+    out.push_str(&format_branches_section(conn));
+
+    // --- Commits ---
+    out.push_str(&format_commits_section(conn));
+
     // --- Prompts ---
     out.push_str(&format_prompts_section(conn));
 
+    // --- Answers ---
+    out.push_str(&format_answers_section(conn));
+
     // --- Plans ---
     out.push_str(&format_plans_section(conn));
 
+    // --- Subagents ---
+    out.push_str(&format_subagents_section(conn));
+
+    // --- Session Events ---
+    out.push_str(&format_session_events_section(conn));
+
+    // --- Conversations ---
+    out.push_str(&format_conversations_section(conn));
+
     // --- Tool Usage ---
     out.push_str(&format_tool_usage_section(conn));
 
+    // --- MCP Servers ---
+    out.push_str(&format_mcp_servers_section(conn));
+
+    // --- Tool Durations ---
+    out.push_str(&format_tool_durations_section(conn));
+
+    // --- Tool Errors ---
+    out.push_str(&format_tool_errors_section(conn));
+
     // --- Top 10 Files Read ---
     out.push_str(&format_top_files_section(conn));
 
+    // --- File Changes ---
+    out.push_str(&format_file_changes_section(conn));
+
     // --- Top 10 Bash Commands ---
     out.push_str(&format_top_bash_section(conn));
 
+    // --- Web Activity ---
+    out.push_str(&format_web_activity_section(conn));
+
     // --- Activity by Date ---
     out.push_str(&format_activity_by_date_section(conn));
 
@@ -204,13 +241,18 @@ fn format_models_section(conn: &Connection) -> String {
     let mut stmt = conn
         .prepare(
             "SELECT model, COUNT(DISTINCT session_id) as sessions,
-                    SUM(input_tokens + output_tokens) as io_tokens
-             FROM token_usage WHERE model IS NOT NULL AND model != ''
+                    SUM(input_tokens + output_tokens) as io_tokens,
+                    SUM(input_tokens), SUM(cache_creation_tokens), SUM(cache_read_tokens), SUM(output_tokens)
+             FROM session_model_usage WHERE model != ''
              GROUP BY model ORDER BY io_tokens DESC",
         )
         .unwrap();
-    let rows: Vec<(String, i64, i64)> = stmt
-        .query_map([], |r| Ok((r.get(0)?, r.get(1)?, r.get(2)?)))
+    let rows: Vec<(String, i64, i64, f64)> = stmt
+        .query_map([], |r| {
+            let model: String = r.get(0)?;
+            let cost = estimate_cost_for_model(&model, r.get(3)?, r.get(4)?, r.get(5)?, r.get(6)?);
+            Ok((model, r.get(1)?, r.get(2)?, cost))
+        })
         .unwrap()
         .filter_map(|r| r.ok())
         .collect();
@@ -218,13 +260,13 @@ fn format_models_section(conn: &Connection) -> String {
     if rows.is_empty() {
         out.push_str("  No model data recorded yet.\n");
     } else {
-        let max_tokens = rows.first().map(|(_, _, t)| *t).unwrap_or(0);
-        let max_name_len = rows.iter().map(|(m, _, _)| m.len()).max().unwrap_or(10);
+        let max_tokens = rows.first().map(|(_, _, t, _)| *t).unwrap_or(0);
+        let max_name_len = rows.iter().map(|(m, _, _, _)| m.len()).max().unwrap_or(10);
         fmt::write(
             &mut out,
             format_args!(
-                "  {:<width$}  {:>8}  {:>8}\n",
-                "Model", "I/O Toks", "Sessions",
+                "  {:<width$}  {:>8}  {:>8}  {:>9}\n",
+                "Model", "I/O Toks", "Sessions", "Est. cost",
                 width = max_name_len,
             ),
         )
@@ -232,21 +274,22 @@ fn format_models_section(conn: &Connection) -> String {
         fmt::write(
             &mut out,
             format_args!(
-                "  {:<width$}  {:>8}  {:>8}\n",
-                "─".repeat(max_name_len), "────────", "────────",
+                "  {:<width$}  {:>8}  {:>8}  {:>9}\n",
+                "─".repeat(max_name_len), "────────", "────────", "─────────",
                 width = max_name_len,
             ),
         )
         .unwrap();
-        for (model, sessions, tokens) in &rows {
+        for (model, sessions, tokens, cost) in &rows {
             let bar = make_bar(*tokens, max_tokens, 20);
             fmt::write(
                 &mut out,
                 format_args!(
-                    "  {:<width$}  {:>8}  {:>8}  {}\n",
+                    "  {:<width$}  {:>8}  {:>8}  {:>9}  {}\n",
                     model,
                     format_number(*tokens),
                     format_number(*sessions),
+                    format_cost(*cost),
                     bar,
                     width = max_name_len,
                 ),
@@ -259,6 +302,334 @@ fn format_models_section(conn: &Connection) -> String {
     out
 }
 
+/// Context window use of main-conversation API calls: overall, per session
+/// (by peak), and the prompts whose turns grew the context most.
+fn format_context_section(conn: &Connection) -> String {
+    let mut out = String::new();
+    out.push_str("--- Context Window ---\n");
+
+    let mut stmt = conn
+        .prepare(
+            "SELECT session_id, turn_id, context_tokens, COALESCE(context_limit, ?1), COALESCE(context_high, 0)
+             FROM api_calls WHERE subagent_id IS NULL AND context_tokens > 0
+             ORDER BY session_id, transcript_offset, timestamp",
+        )
+        .unwrap();
+    let calls: Vec<(String, Option<i64>, i64, i64, bool)> = stmt
+        .query_map([context::DEFAULT_LIMIT], |r| Ok((r.get(0)?, r.get(1)?, r.get(2)?, r.get(3)?, r.get(4)?)))
+        .unwrap()
+        .filter_map(|r| r.ok())
+        .collect();
+    if calls.is_empty() {
+        out.push_str("  No context sizes recorded yet.\n\n");
+        return out;
+    }
+
+    // session -> (calls, total tokens, peak tokens, limit at peak, high calls)
+    let mut sessions: BTreeMap<&str, (i64, i64, i64, i64, i64)> = BTreeMap::new();
+    // (session, turn) -> (context before the turn's first call, peak in the turn)
+    let mut turns: BTreeMap<(&str, i64), (i64, i64)> = BTreeMap::new();
+    let mut previous: Option<(&str, i64)> = None;
+    for (session, turn_id, tokens, limit, high) in &calls {
+        let entry = sessions.entry(session).or_default();
+        entry.0 += 1;
+        entry.1 += tokens;
+        if *tokens > entry.2 {
+            entry.2 = *tokens;
+            entry.3 = *limit;
+        }
+        entry.4 += *high as i64;
+
+        if let Some(turn_id) = turn_id {
+            let base = match previous {
+                Some((s, t)) if s == session => t,
+                _ => 0,
+            };
+            let turn = turns.entry((session, *turn_id)).or_insert((base, 0));
+            turn.1 = turn.1.max(*tokens);
+        }
+        previous = Some((session, *tokens));
+    }
+    // Turns after a compaction start below their base and are left out
+    let mut growth: Vec<(i64, &str, i64)> = turns
+        .into_iter()
+        .filter(|(_, (base, peak))| peak > base)
+        .map(|((session, turn_id), (base, peak))| (peak - base, session, turn_id))
+        .collect();
+
+    let total_calls = calls.len() as i64;
+    let total_tokens: i64 = calls.iter().map(|c| c.2).sum();
+    let total_limit: i64 = calls.iter().map(|c| c.3).sum();
+    let high_calls: i64 = sessions.values().map(|s| s.4).sum();
+    let high_sessions = sessions.values().filter(|s| s.4 > 0).count();
+    let pct = |n: i64, of: i64| if of > 0 { n as f64 / of as f64 * 100.0 } else { 0.0 };
+    fmt::write(&mut out, format_args!("  Calls measured:  {:>10}\n", format_number(total_calls))).unwrap();
+    fmt::write(
+        &mut out,
+        format_args!(
+            "  Avg context:     {:>10} ({:.1}% of window)\n",
+            format_number(total_tokens / total_calls),
+            pct(total_tokens, total_limit),
+        ),
+    )
+    .unwrap();
+    fmt::write(
+        &mut out,
+        format_args!(
+            "  Calls above {}%: {:>10} ({:.1}%) in {} sessions\n",
+            context::HIGH_USE_PERCENT,
+            format_number(high_calls),
+            pct(high_calls, total_calls),
+            high_sessions,
+        ),
+    )
+    .unwrap();
+
+    let mut stmt = conn
+        .prepare("SELECT COALESCE(session_id, ''), context_tokens FROM compactions")
+        .unwrap();
+    let compactions: Vec<(String, Option<i64>)> = stmt
+        .query_map([], |r| Ok((r.get(0)?, r.get(1)?)))
+        .unwrap()
+        .filter_map(|r| r.ok())
+        .collect();
+    let before: Vec<i64> = compactions.iter().filter_map(|c| c.1).collect();
+    if !before.is_empty() {
+        fmt::write(
+            &mut out,
+            format_args!(
+                "  Compactions:     {:>10} (avg {} tokens before)\n",
+                format_number(compactions.len() as i64),
+                format_number(before.iter().sum::<i64>() / before.len() as i64),
+            ),
+        )
+        .unwrap();
+    }
+
+    let mut by_peak: Vec<_> = sessions.iter().collect();
+    by_peak.sort_by(|a, b| b.1 .2.cmp(&a.1 .2).then(a.0.cmp(b.0)));
+    out.push_str("Peak context by session:\n");
+    fmt::write(
+        &mut out,
+        format_args!(
+            "  {:<8}  {:>5}  {:>8}  {:>8}  {:>6}  {:>5}  {:>7}  {}\n",
+            "Session", "Calls", "Avg", "Peak", "Peak %", "High", "Compact", "Project"
+        ),
+    )
+    .unwrap();
+    fmt::write(
+        &mut out,
+        format_args!(
+            "  {:<8}  {:>5}  {:>8}  {:>8}  {:>6}  {:>5}  {:>7}  {}\n",
+            "────────", "─────", "────────", "────────", "──────", "─────", "───────", "───────"
+        ),
+    )
+    .unwrap();
+    for (session, (count, tokens, peak, limit, high)) in by_peak.iter().take(10) {
+        let compacted = compactions.iter().filter(|c| c.0 == **session).count();
+        let cwd: String = conn
+            .query_row("SELECT COALESCE(cwd, '') FROM sessions WHERE session_id = ?1", [session], |r| r.get(0))
+            .unwrap_or_default();
+        let (root, _) = extract_project_info(&cwd);
+        let short: String = session.chars().take(8).collect();
+        fmt::write(
+            &mut out,
+            format_args!(
+                "  {:<8}  {:>5}  {:>8}  {:>8}  {:>5.1}%  {:>5}  {:>7}  {}\n",
+                short,
+                format_number(*count),
+                format_number(tokens / count),
+                format_number(*peak),
+                pct(*peak, *limit),
+                format_number(*high),
+                compacted,
+                shorten_path(&root, 40),
+            ),
+        )
+        .unwrap();
+    }
+
+    growth.sort_by(|a, b| b.0.cmp(&a.0).then(a.1.cmp(b.1)).then(a.2.cmp(&b.2)));
+    if !growth.is_empty() {
+        out.push_str("Prompts that grew context most:\n");
+        fmt::write(&mut out, format_args!("  {:>9}  {:<8}  {}\n", "Growth", "Session", "Prompt")).unwrap();
+        fmt::write(&mut out, format_args!("  {:>9}  {:<8}  {}\n", "─────────", "────────", "──────")).unwrap();
+        for (tokens, session, turn_id) in growth.iter().take(5) {
+            let text: String = conn
+                .query_row(
+                    "SELECT COALESCE(p.prompt_text, '') FROM turns t JOIN prompts p ON p.id = t.prompt_id
+                     WHERE t.id = ?1",
+                    [turn_id],
+                    |r| r.get(0),
+                )
+                .unwrap_or_default();
+            let text = text.replace('\n', " ");
+            let text = if text.chars().count() > 50 {
+                format!("{}...", text.chars().take(47).collect::<String>())
+            } else {
+                text
+            };
+            let short: String = session.chars().take(8).collect();
+            fmt::write(
+                &mut out,
+                format_args!("  {:>9}  {:<8}  {}\n", format!("+{}", format_number(*tokens)), short, text),
+            )
+            .unwrap();
+        }
+    }
+
+    out.push('\n');
+    out
+}
+
+/// Cost per (project, git branch). Each API call takes the branch recorded at
+/// its turn's Stop, falling back to the session's starting branch.
+fn format_branches_section(conn: &Connection) -> String {
+    let mut out = String::new();
+    out.push_str("--- Cost by Branch ---\n");
+
+    let mut stmt = conn
+        .prepare(
+            "SELECT a.session_id, COALESCE(s.cwd, ''), COALESCE(t.git_branch, s.git_branch), COALESCE(a.model, ''), COUNT(*),
+                    SUM(a.input_tokens), SUM(a.cache_creation_tokens), SUM(a.cache_read_tokens), SUM(a.output_tokens)
+             FROM api_calls a
+             JOIN sessions s ON s.session_id = a.session_id
+             LEFT JOIN turns t ON t.id = a.turn_id
+             WHERE COALESCE(t.git_branch, s.git_branch) IS NOT NULL
+             GROUP BY 1, 2, 3, 4",
+        )
+        .unwrap();
+    let rows: Vec<(String, String, String, i64, f64)> = stmt
+        .query_map([], |r| {
+            let model: String = r.get(3)?;
+            let cost = estimate_cost_for_model(&model, r.get(5)?, r.get(6)?, r.get(7)?, r.get(8)?);
+            Ok((r.get(0)?, r.get(1)?, r.get(2)?, r.get(4)?, cost))
+        })
+        .unwrap()
+        .filter_map(|r| r.ok())
+        .collect();
+
+    if rows.is_empty() {
+        out.push_str("  No git context recorded yet.\n\n");
+        return out;
+    }
+
+    // (project, branch) -> (sessions, api calls, cost)
+    let mut by_branch: BTreeMap<(String, String), (BTreeSet<String>, i64, f64)> = BTreeMap::new();
+    for (session_id, cwd, branch, calls, cost) in rows {
+        let (root, _) = extract_project_info(&cwd);
+        let project = root.rsplit('/').next().unwrap_or_default().to_string();
+        let entry = by_branch.entry((project, branch)).or_default();
+        entry.0.insert(session_id);
+        entry.1 += calls;
+        entry.2 += cost;
+    }
+    let mut sorted: Vec<_> = by_branch.into_iter().collect();
+    sorted.sort_by(|a, b| b.1 .2.total_cmp(&a.1 .2));
+
+    let pw = sorted.iter().map(|((p, _), _)| p.len()).max().unwrap_or(7).max(7);
+    let bw = sorted.iter().map(|((_, b), _)| b.len()).max().unwrap_or(6).max(6);
+    fmt::write(
+        &mut out,
+        format_args!(
+            "  {:<pw$}  {:<bw$}  {:>8}  {:>9}  {:>9}\n",
+            "Project", "Branch", "Sessions", "API calls", "Est. cost",
+        ),
+    )
+    .unwrap();
+    for ((project, branch), (sessions, calls, cost)) in sorted.iter().take(15) {
+        fmt::write(
+            &mut out,
+            format_args!(
+                "  {:<pw$}  {:<bw$}  {:>8}  {:>9}  {:>9}\n",
+                project,
+                branch,
+                format_number(sessions.len() as i64),
+                format_number(*calls),
+                format_cost(*cost),
+            ),
+        )
+        .unwrap();
+    }
+
+    out.push('\n');
+    out
+}
+
+/// Commits recorded by the git hooks and the share of their added lines the
+/// agent wrote, overall and per repository.
+fn format_commits_section(conn: &Connection) -> String {
+    let mut out = String::new();
+    out.push_str("--- Commits ---\n");
+
+    let mut stmt = conn
+        .prepare(
+            "SELECT COALESCE(repo, ''), COUNT(*), COUNT(session_id),
+                    COALESCE(SUM(agent_lines), 0), COALESCE(SUM(human_added), 0), COALESCE(SUM(human_removed), 0)
+             FROM commits GROUP BY 1 ORDER BY 2 DESC",
+        )
+        .unwrap();
+    let rows: Vec<(String, i64, i64, i64, i64, i64)> = stmt
+        .query_map([], |r| Ok((r.get(0)?, r.get(1)?, r.get(2)?, r.get(3)?, r.get(4)?, r.get(5)?)))
+        .unwrap()
+        .filter_map(|r| r.ok())
+        .collect();
+    if rows.is_empty() {
+        out.push_str("  No commits recorded yet (see `claude-track install --git`).\n\n");
+        return out;
+    }
+
+    let share = |agent: i64, human: i64| {
+        if agent + human > 0 {
+            format!("{:.1}%", agent as f64 * 100.0 / (agent + human) as f64)
+        } else {
+            "-".to_string()
+        }
+    };
+    let (commits, linked, agent, human, removed) = rows.iter().fold((0, 0, 0, 0, 0), |acc, r| {
+        (acc.0 + r.1, acc.1 + r.2, acc.2 + r.3, acc.3 + r.4, acc.4 + r.5)
+    });
+    fmt::write(
+        &mut out,
+        format_args!(
+            "  Commits: {} ({} from Claude Code sessions)\n  Lines added: {} by agent, {} by hand ({} removed by hand)\n  Agent share of added lines: {}\n",
+            format_number(commits),
+            format_number(linked),
+            format_number(agent),
+            format_number(human),
+            format_number(removed),
+            share(agent, human),
+        ),
+    )
+    .unwrap();
+
+    if rows.len() > 1 {
+        let width = rows.iter().map(|r| r.0.len()).max().unwrap_or(4).max(4);
+        fmt::write(
+            &mut out,
+            format_args!("\n  {:<width$}  {:>7}  {:>11}  {:>11}  {:>11}\n", "Repo", "Commits", "Agent lines", "Human lines", "Agent share"),
+        )
+        .unwrap();
+        for (repo, commits, _, agent, human, _) in &rows {
+            fmt::write(
+                &mut out,
+                format_args!(
+                    "  {:<width$}  {:>7}  {:>11}  {:>11}  {:>11}\n",
+                    repo,
+                    format_number(*commits),
+                    format_number(*agent),
+                    format_number(*human),
+                    share(*agent, *human),
+                ),
+            )
+            .unwrap();
+        }
+    }
+
+    out.push('\n');
+    out
+}
+
 fn format_tokens_section(conn: &Connection) -> String {
     let mut out = String::new();
     out.push_str("--- Token Usage ---\n");
@@ -277,7 +648,7 @@ fn format_tokens_section(conn: &Connection) -> String {
                 COALESCE(SUM(cache_read_tokens), 0),
                 COALESCE(SUM(output_tokens), 0),
                 COALESCE(SUM(api_call_count), 0)
-            FROM token_usage",
+            FROM session_model_usage",
             [],
             |r| Ok((r.get(0)?, r.get(1)?, r.get(2)?, r.get(3)?, r.get(4)?)),
         )
@@ -322,8 +693,8 @@ fn format_tokens_section(conn: &Connection) -> String {
     // Per-model cost breakdown
     let mut stmt = conn
         .prepare(
-            "SELECT COALESCE(model, ''), SUM(input_tokens), SUM(cache_creation_tokens), SUM(cache_read_tokens), SUM(output_tokens)
-             FROM token_usage GROUP BY model",
+            "SELECT model, SUM(input_tokens), SUM(cache_creation_tokens), SUM(cache_read_tokens), SUM(output_tokens)
+             FROM session_model_usage GROUP BY model",
         )
         .unwrap();
     let model_rows: Vec<(String, i64, i64, i64, i64)> = stmt
@@ -390,7 +761,8 @@ fn format_prompts_section(conn: &Connection) -> String {
 
     let avg_length: f64 = conn
         .query_row(
-            "SELECT COALESCE(AVG(LENGTH(prompt_text)), 0) FROM prompts",
+            "SELECT COALESCE(AVG(COALESCE(prompt_length, LENGTH(prompt_text))), 0) FROM prompts
+             WHERE capture_level IS NOT 'none'",
             [],
             |r| r.get(0),
         )
@@ -405,6 +777,70 @@ fn format_prompts_section(conn: &Connection) -> String {
     out
 }
 
+/// Answer length buckets in chars, as (label, upper bound).
+const ANSWER_BUCKETS: &[(&str, i64)] = &[
+    ("< 200", 200),
+    ("200-1k", 1_000),
+    ("1k-5k", 5_000),
+    ("5k-20k", 20_000),
+    ("20k+", i64::MAX),
+];
+
+fn format_answers_section(conn: &Connection) -> String {
+    let mut out = String::new();
+    out.push_str("--- Answers ---\n");
+
+    let mut stmt = conn
+        .prepare("SELECT answer_length FROM turns WHERE answer_length IS NOT NULL ORDER BY answer_length")
+        .unwrap();
+    let lengths: Vec<i64> = stmt
+        .query_map([], |r| r.get(0))
+        .unwrap()
+        .filter_map(|r| r.ok())
+        .collect();
+    let continued: i64 = conn
+        .query_row("SELECT COUNT(*) FROM turns WHERE stop_hook_active = 1", [], |r| r.get(0))
+        .unwrap_or(0);
+    if lengths.is_empty() {
+        out.push_str("  No answers recorded yet.\n\n");
+        return out;
+    }
+
+    let avg = lengths.iter().sum::<i64>() as f64 / lengths.len() as f64;
+    fmt::write(&mut out, format_args!("  Answered turns:  {:>10}\n", format_number(lengths.len() as i64))).unwrap();
+    fmt::write(
+        &mut out,
+        format_args!(
+            "  Length (chars):  p50 {}  p90 {}  max {}  avg {}\n",
+            format_number(percentile(&lengths, 50.0)),
+            format_number(percentile(&lengths, 90.0)),
+            format_number(*lengths.last().unwrap_or(&0)),
+            format_number(avg as i64),
+        ),
+    )
+    .unwrap();
+
+    let mut counts = vec![0i64; ANSWER_BUCKETS.len()];
+    for len in &lengths {
+        let bucket = ANSWER_BUCKETS.iter().position(|(_, upper)| len < upper).unwrap_or(0);
+        counts[bucket] += 1;
+    }
+    let max_count = counts.iter().copied().max().unwrap_or(0);
+    for ((label, _), count) in ANSWER_BUCKETS.iter().zip(&counts) {
+        fmt::write(
+            &mut out,
+            format_args!("  {:>8}  {:>6}  {}\n", label, format_number(*count), make_bar(*count, max_count, 20)),
+        )
+        .unwrap();
+    }
+    if continued > 0 {
+        fmt::write(&mut out, format_args!("  Continued by a Stop hook: {} turns\n", format_number(continued))).unwrap();
+    }
+
+    out.push('\n');
+    out
+}
+
 fn format_plans_section(conn: &Connection) -> String {
     let mut out = String::new();
     out.push_str("--- Plans ---\n");
@@ -415,6 +851,222 @@ fn format_plans_section(conn: &Connection) -> String {
 
     fmt::write(&mut out, format_args!("  Total plans:     {:>10}\n", format_number(total))).unwrap();
 
+    // Plans in order within each session; an accepted plan ends a round of
+    // revisions, so its iterations are the plans since the previous accepted one
+    let outcomes: Vec<(String, Option<bool>)> = conn
+        .prepare("SELECT session_id, accepted FROM plans ORDER BY session_id, timestamp, id")
+        .and_then(|mut stmt| stmt.query_map([], |r| Ok((r.get(0)?, r.get(1)?)))?.collect())
+        .unwrap_or_default();
+    let accepted = outcomes.iter().filter(|(_, a)| *a == Some(true)).count();
+    let rejected = outcomes.iter().filter(|(_, a)| *a == Some(false)).count();
+    let resolved = accepted + rejected;
+    if resolved > 0 {
+        let mut iterations = 0;
+        let mut since_accepted = 0;
+        let mut session = "";
+        for (session_id, outcome) in &outcomes {
+            if session_id != session {
+                session = session_id;
+                since_accepted = 0;
+            }
+            since_accepted += 1;
+            if *outcome == Some(true) {
+                iterations += since_accepted;
+                since_accepted = 0;
+            }
+        }
+        fmt::write(
+            &mut out,
+            format_args!(
+                "  Accepted:        {:>10} ({:.1}%)\n",
+                format_number(accepted as i64),
+                accepted as f64 * 100.0 / resolved as f64
+            ),
+        )
+        .unwrap();
+        fmt::write(&mut out, format_args!("  Rejected:        {:>10}\n", format_number(rejected as i64))).unwrap();
+        if accepted > 0 {
+            fmt::write(
+                &mut out,
+                format_args!("  Iterations per accepted plan: {:.1}\n", iterations as f64 / accepted as f64),
+            )
+            .unwrap();
+        }
+    }
+
+    out.push('\n');
+    out
+}
+
+fn format_session_events_section(conn: &Connection) -> String {
+    let mut out = String::new();
+    out.push_str("--- Session Events ---\n");
+
+    let count = |sql: &str| -> i64 { conn.query_row(sql, [], |r| r.get(0)).unwrap_or(0) };
+
+    let total_sessions = count("SELECT COUNT(*) FROM sessions");
+    let pct = |n: i64| -> String {
+        if total_sessions > 0 {
+            format!(" ({:.1}%)", n as f64 / total_sessions as f64 * 100.0)
+        } else {
+            String::new()
+        }
+    };
+
+    let permission = count("SELECT COUNT(*) FROM notifications WHERE notification_type = 'permission_prompt'");
+    let idle = count("SELECT COUNT(*) FROM notifications WHERE notification_type = 'idle_prompt'");
+    let other = count(
+        "SELECT COUNT(*) FROM notifications
+         WHERE notification_type NOT IN ('permission_prompt', 'idle_prompt')",
+    );
+    let stalled_sessions = count(
+        "SELECT COUNT(DISTINCT session_id) FROM notifications WHERE notification_type = 'permission_prompt'",
+    );
+    fmt::write(&mut out, format_args!("  Permission prompts: {:>10}\n", format_number(permission))).unwrap();
+    fmt::write(&mut out, format_args!("  Idle prompts:       {:>10}\n", format_number(idle))).unwrap();
+    if other > 0 {
+        fmt::write(&mut out, format_args!("  Other notices:      {:>10}\n", format_number(other))).unwrap();
+    }
+    fmt::write(
+        &mut out,
+        format_args!("  Sessions prompted:  {:>10}{}\n", format_number(stalled_sessions), pct(stalled_sessions)),
+    )
+    .unwrap();
+
+    let subagent_stops = count("SELECT COUNT(*) FROM subagent_stops");
+    fmt::write(&mut out, format_args!("  Subagent stops:     {:>10}\n", format_number(subagent_stops))).unwrap();
+
+    let auto = count("SELECT COUNT(*) FROM compactions WHERE trigger = 'auto'");
+    let total_compactions = count("SELECT COUNT(*) FROM compactions");
+    let compacted_sessions = count("SELECT COUNT(DISTINCT session_id) FROM compactions");
+    fmt::write(
+        &mut out,
+        format_args!(
+            "  Compactions:        {:>10} ({} auto, {} manual)\n",
+            format_number(total_compactions),
+            format_number(auto),
+            format_number(total_compactions - auto),
+        ),
+    )
+    .unwrap();
+    fmt::write(
+        &mut out,
+        format_args!("  Sessions compacted: {:>10}{}\n", format_number(compacted_sessions), pct(compacted_sessions)),
+    )
+    .unwrap();
+
+    out.push('\n');
+    out
+}
+
+/// Sessions joined by `session_links` into conversations: how they were
+/// linked, and the longest chains by session count.
+fn format_conversations_section(conn: &Connection) -> String {
+    let mut out = String::new();
+    out.push_str("--- Conversations ---\n");
+
+    let mut stmt = conn
+        .prepare("SELECT COALESCE(reason, ''), COUNT(*) FROM session_links GROUP BY 1 ORDER BY 2 DESC, 1")
+        .unwrap();
+    let reasons: Vec<(String, i64)> = stmt
+        .query_map([], |r| Ok((r.get(0)?, r.get(1)?)))
+        .unwrap()
+        .filter_map(|r| r.ok())
+        .collect();
+    if reasons.is_empty() {
+        out.push_str("  No linked sessions recorded yet.\n\n");
+        return out;
+    }
+
+    let mut stmt = conn
+        .prepare("SELECT session_id FROM session_links UNION SELECT previous_session_id FROM session_links")
+        .unwrap();
+    let linked: Vec<String> = stmt
+        .query_map([], |r| r.get(0))
+        .unwrap()
+        .filter_map(|r| r.ok())
+        .collect();
+    let mut seen: BTreeSet<String> = BTreeSet::new();
+    let mut chains: Vec<Vec<String>> = Vec::new();
+    for id in linked {
+        if seen.contains(&id) {
+            continue;
+        }
+        let chain = db::get_session_chain(conn, &id).unwrap_or_else(|_| vec![id]);
+        seen.extend(chain.iter().cloned());
+        chains.push(chain);
+    }
+
+    fmt::write(
+        &mut out,
+        format_args!(
+            "  Conversations:   {:>10} ({} sessions)\n",
+            format_number(chains.len() as i64),
+            format_number(seen.len() as i64),
+        ),
+    )
+    .unwrap();
+    let links: Vec<String> = reasons.iter().map(|(reason, n)| format!("{reason} {n}")).collect();
+    fmt::write(&mut out, format_args!("  Links:           {}\n", links.join(", "))).unwrap();
+
+    // (sessions, prompts, combined seconds, first session, project root)
+    let mut rows: Vec<(usize, i64, i64, String, String)> = chains
+        .iter()
+        .map(|chain| {
+            let (mut prompts, mut seconds) = (0, 0);
+            for id in chain {
+                prompts += conn
+                    .query_row("SELECT COUNT(*) FROM prompts WHERE session_id = ?1", [id], |r| r.get::<_, i64>(0))
+                    .unwrap_or(0);
+                seconds += conn
+                    .query_row(
+                        "SELECT COALESCE(CAST(ROUND((julianday(ended_at) - julianday(started_at)) * 86400) AS INTEGER), 0)
+                         FROM sessions WHERE session_id = ?1",
+                        [id],
+                        |r| r.get::<_, i64>(0),
+                    )
+                    .unwrap_or(0);
+            }
+            let cwd: String = conn
+                .query_row(
+                    "SELECT COALESCE(cwd, '') FROM sessions WHERE session_id = ?1",
+                    [&chain[0]],
+                    |r| r.get(0),
+                )
+                .unwrap_or_default();
+            let (root, _) = extract_project_info(&cwd);
+            (chain.len(), prompts, seconds, chain[0].clone(), root)
+        })
+        .collect();
+    rows.sort_by(|a, b| b.0.cmp(&a.0).then(b.1.cmp(&a.1)).then(a.3.cmp(&b.3)));
+
+    out.push_str("Longest conversations:\n");
+    fmt::write(
+        &mut out,
+        format_args!("  {:>8}  {:>7}  {:>9}  {:<8}  {}\n", "Sessions", "Prompts", "Time", "First", "Project"),
+    )
+    .unwrap();
+    fmt::write(
+        &mut out,
+        format_args!("  {:>8}  {:>7}  {:>9}  {:<8}  {}\n", "────────", "───────", "─────────", "────────", "───────"),
+    )
+    .unwrap();
+    for (sessions, prompts, seconds, first, root) in rows.iter().take(10) {
+        let short: String = first.chars().take(8).collect();
+        fmt::write(
+            &mut out,
+            format_args!(
+                "  {:>8}  {:>7}  {:>9}  {:<8}  {}\n",
+                sessions,
+                format_number(*prompts),
+                format_duration(*seconds),
+                short,
+                shorten_path(root, 50),
+            ),
+        )
+        .unwrap();
+    }
+
     out.push('\n');
     out
 }
@@ -430,8 +1082,8 @@ fn format_tool_usage_section(conn: &Connection) -> String {
 
     let mut stmt = conn
         .prepare(
-            "SELECT tool_name, COUNT(*) as cnt FROM tool_uses
-             GROUP BY tool_name ORDER BY cnt DESC",
+            "SELECT CASE WHEN mcp_server IS NULL THEN tool_name ELSE 'MCP tools' END AS name, COUNT(*) as cnt
+             FROM tool_uses GROUP BY name ORDER BY cnt DESC",
         )
         .unwrap();
     let rows: Vec<(String, i64)> = stmt
@@ -467,6 +1119,495 @@ fn format_tool_usage_section(conn: &Connection) -> String {
     out
 }
 
+fn format_subagents_section(conn: &Connection) -> String {
+    let mut out = String::new();
+    out.push_str("--- Subagents ---\n");
+
+    // type -> (runs, total duration ms, timed runs, tool calls, api calls, cost)
+    let mut by_type: BTreeMap<String, (i64, i64, i64, i64, i64, f64)> = BTreeMap::new();
+
+    let mut stmt = conn
+        .prepare(
+            "SELECT COALESCE(NULLIF(subagent_type, ''), 'unknown'), COUNT(*),
+                    COALESCE(SUM(duration_ms), 0), COUNT(duration_ms)
+             FROM subagents GROUP BY 1",
+        )
+        .unwrap();
+    let rows: Vec<(String, i64, i64, i64)> = stmt
+        .query_map([], |r| Ok((r.get(0)?, r.get(1)?, r.get(2)?, r.get(3)?)))
+        .unwrap()
+        .filter_map(|r| r.ok())
+        .collect();
+    if rows.is_empty() {
+        out.push_str("  No subagents recorded yet.\n\n");
+        return out;
+    }
+    for (kind, runs, total_ms, timed) in rows {
+        let entry = by_type.entry(kind).or_default();
+        entry.0 = runs;
+        entry.1 = total_ms;
+        entry.2 = timed;
+    }
+
+    let mut stmt = conn
+        .prepare(
+            "SELECT COALESCE(NULLIF(s.subagent_type, ''), 'unknown'), COUNT(*)
+             FROM tool_uses t JOIN subagents s ON t.subagent_id = s.tool_use_id
+             GROUP BY 1",
+        )
+        .unwrap();
+    let rows: Vec<(String, i64)> = stmt
+        .query_map([], |r| Ok((r.get(0)?, r.get(1)?)))
+        .unwrap()
+        .filter_map(|r| r.ok())
+        .collect();
+    for (kind, tool_calls) in rows {
+        by_type.entry(kind).or_default().3 = tool_calls;
+    }
+
+    // Cost each (type, model) slice at its own model's rates
+    let mut stmt = conn
+        .prepare(
+            "SELECT COALESCE(NULLIF(s.subagent_type, ''), 'unknown'), COALESCE(a.model, ''), COUNT(*),
+                    SUM(a.input_tokens), SUM(a.cache_creation_tokens), SUM(a.cache_read_tokens), SUM(a.output_tokens)
+             FROM api_calls a JOIN subagents s ON a.subagent_id = s.tool_use_id
+             GROUP BY 1, 2",
+        )
+        .unwrap();
+    let rows: Vec<(String, i64, f64)> = stmt
+        .query_map([], |r| {
+            let model: String = r.get(1)?;
+            let cost = estimate_cost_for_model(&model, r.get(3)?, r.get(4)?, r.get(5)?, r.get(6)?);
+            Ok((r.get(0)?, r.get(2)?, cost))
+        })
+        .unwrap()
+        .filter_map(|r| r.ok())
+        .collect();
+    for (kind, calls, cost) in rows {
+        let entry = by_type.entry(kind).or_default();
+        entry.4 += calls;
+        entry.5 += cost;
+    }
+
+    let mut sorted: Vec<_> = by_type.into_iter().collect();
+    sorted.sort_by(|a, b| b.1 .5.total_cmp(&a.1 .5).then(b.1 .0.cmp(&a.1 .0)));
+
+    let width = sorted.iter().map(|(k, _)| k.len()).max().unwrap_or(4).max(4);
+    fmt::write(
+        &mut out,
+        format_args!(
+            "  {:<width$}  {:>5}  {:>8}  {:>10}  {:>9}  {:>9}\n",
+            "Type", "Runs", "Avg time", "Tool calls", "API calls", "Est. cost",
+        ),
+    )
+    .unwrap();
+    let mut subagent_cost = 0.0;
+    for (kind, (runs, total_ms, timed, tool_calls, api_calls, cost)) in &sorted {
+        let avg = if *timed > 0 { format_duration_ms(total_ms / timed) } else { "-".to_string() };
+        fmt::write(
+            &mut out,
+            format_args!(
+                "  {:<width$}  {:>5}  {:>8}  {:>10}  {:>9}  {:>9}\n",
+                kind,
+                format_number(*runs),
+                avg,
+                format_number(*tool_calls),
+                format_number(*api_calls),
+                format_cost(*cost),
+            ),
+        )
+        .unwrap();
+        subagent_cost += cost;
+    }
+
+    let mut stmt = conn
+        .prepare(
+            "SELECT model, SUM(input_tokens), SUM(cache_creation_tokens), SUM(cache_read_tokens), SUM(output_tokens)
+             FROM session_model_usage GROUP BY model",
+        )
+        .unwrap();
+    let total_cost: f64 = stmt
+        .query_map([], |r| {
+            let model: String = r.get(0)?;
+            Ok(estimate_cost_for_model(&model, r.get(1)?, r.get(2)?, r.get(3)?, r.get(4)?))
+        })
+        .unwrap()
+        .filter_map(|r| r.ok())
+        .sum();
+    if total_cost > 0.0 {
+        fmt::write(
+            &mut out,
+            format_args!(
+                "  Subagent share of cost: {:.1}% ({} of {})\n",
+                subagent_cost / total_cost * 100.0,
+                format_cost(subagent_cost),
+                format_cost(total_cost),
+            ),
+        )
+        .unwrap();
+    }
+
+    out.push('\n');
+    out
+}
+
+fn format_tool_errors_section(conn: &Connection) -> String {
+    let mut out = String::new();
+    out.push_str("--- Tool Errors ---\n");
+
+    let mut stmt = conn
+        .prepare(
+            "SELECT tool_name, COALESCE(cwd, ''), status, COUNT(*) FROM tool_uses
+             WHERE status IS NOT NULL
+             GROUP BY tool_name, cwd, status",
+        )
+        .unwrap();
+    let rows: Vec<(String, String, String, i64)> = stmt
+        .query_map([], |r| Ok((r.get(0)?, r.get(1)?, r.get(2)?, r.get(3)?)))
+        .unwrap()
+        .filter_map(|r| r.ok())
+        .collect();
+
+    // Calls with neither a PostToolUse nor a transcript result yet
+    let unresolved: i64 = conn
+        .query_row(
+            "SELECT COUNT(*) FROM tool_uses WHERE status IS NULL AND tool_use_id IS NOT NULL",
+            [],
+            |r| r.get(0),
+        )
+        .unwrap_or(0);
+    let unresolved_line = format!("Unresolved: {} calls with no recorded outcome\n", format_number(unresolved));
+
+    if rows.is_empty() {
+        out.push_str("  No tool outcomes recorded yet.\n");
+        if unresolved > 0 {
+            out.push_str(&format!("  {unresolved_line}"));
+        }
+        out.push('\n');
+        return out;
+    }
+
+    // name -> [calls, errors, denied, interrupted]
+    let mut by_tool: BTreeMap<String, [i64; 4]> = BTreeMap::new();
+    let mut by_project: BTreeMap<String, [i64; 4]> = BTreeMap::new();
+    for (tool, cwd, status, count) in &rows {
+        let (root, _) = extract_project_info(cwd);
+        for counts in [
+            by_tool.entry(tool.clone()).or_default(),
+            by_project.entry(root).or_default(),
+        ] {
+            counts[0] += count;
+            match status.as_str() {
+                "error" => counts[1] += count,
+                "denied" => counts[2] += count,
+                "interrupted" => counts[3] += count,
+                _ => {}
+            }
+        }
+    }
+
+    let (calls, errors) = by_tool
+        .values()
+        .fold((0, 0), |(c, e), counts| (c + counts[0], e + counts[1]));
+    fmt::write(
+        &mut out,
+        format_args!(
+            "Overall error rate: {:.1}% ({} of {} calls)\n",
+            errors as f64 / calls as f64 * 100.0,
+            format_number(errors),
+            format_number(calls),
+        ),
+    )
+    .unwrap();
+    if unresolved > 0 {
+        out.push_str(&unresolved_line);
+    }
+
+    for (heading, table, is_path) in [("By tool:", by_tool, false), ("By project:", by_project, true)] {
+        let mut sorted: Vec<(String, [i64; 4])> = table
+            .into_iter()
+            .filter(|(name, _)| !name.is_empty())
+            .collect();
+        // Highest error rate first, ties broken by volume
+        sorted.sort_by(|a, b| {
+            let rate = |c: &[i64; 4]| c[1] as f64 / c[0] as f64;
+            rate(&b.1).total_cmp(&rate(&a.1)).then(b.1[0].cmp(&a.1[0]))
+        });
+        let names: Vec<String> = sorted
+            .iter()
+            .map(|(n, _)| if is_path { shorten_path(n, 50) } else { n.clone() })
+            .collect();
+        let width = names.iter().map(|n| n.chars().count()).max().unwrap_or(4).max(4);
+
+        fmt::write(&mut out, format_args!("\n  {heading}\n")).unwrap();
+        fmt::write(
+            &mut out,
+            format_args!(
+                "  {:<width$}  {:>6}  {:>6}  {:>6}  {:>11}  {:>7}\n",
+                "Name", "Calls", "Errors", "Denied", "Interrupted", "Error %",
+            ),
+        )
+        .unwrap();
+        for (name, (_, c)) in names.iter().zip(&sorted) {
+            fmt::write(
+                &mut out,
+                format_args!(
+                    "  {:<width$}  {:>6}  {:>6}  {:>6}  {:>11}  {:>6.1}%\n",
+                    name,
+                    format_number(c[0]),
+                    format_number(c[1]),
+                    format_number(c[2]),
+                    format_number(c[3]),
+                    c[1] as f64 / c[0] as f64 * 100.0,
+                ),
+            )
+            .unwrap();
+        }
+    }
+
+    out.push('\n');
+    out
+}
+
+fn format_mcp_servers_section(conn: &Connection) -> String {
+    let mut out = String::new();
+    out.push_str("--- MCP Servers ---\n");
+
+    let mut stmt = conn
+        .prepare(
+            "SELECT mcp_server, mcp_tool, status, duration_ms FROM tool_uses
+             WHERE mcp_server IS NOT NULL
+             ORDER BY mcp_server, duration_ms",
+        )
+        .unwrap();
+    let rows: Vec<(String, String, Option<String>, Option<i64>)> = stmt
+        .query_map([], |r| Ok((r.get(0)?, r.get(1)?, r.get(2)?, r.get(3)?)))
+        .unwrap()
+        .filter_map(|r| r.ok())
+        .collect();
+    if rows.is_empty() {
+        out.push_str("  No MCP tool calls recorded yet.\n\n");
+        return out;
+    }
+
+    struct Server {
+        calls: i64,
+        tools: BTreeSet<String>,
+        with_outcome: i64,
+        errors: i64,
+        // Sorted ascending by the query
+        durations: Vec<i64>,
+    }
+    let mut by_server: BTreeMap<String, Server> = BTreeMap::new();
+    for (server, tool, status, duration) in rows {
+        let entry = by_server.entry(server).or_insert_with(|| Server {
+            calls: 0,
+            tools: BTreeSet::new(),
+            with_outcome: 0,
+            errors: 0,
+            durations: Vec::new(),
+        });
+        entry.calls += 1;
+        entry.tools.insert(tool);
+        if let Some(status) = status {
+            entry.with_outcome += 1;
+            entry.errors += (status == "error") as i64;
+        }
+        if let Some(ms) = duration.filter(|ms| *ms >= 0) {
+            entry.durations.push(ms);
+        }
+    }
+    let mut servers: Vec<(String, Server)> = by_server.into_iter().collect();
+    servers.sort_by(|a, b| b.1.calls.cmp(&a.1.calls).then(a.0.cmp(&b.0)));
+
+    let width = servers.iter().map(|(n, _)| n.chars().count()).max().unwrap_or(6).max(6);
+    fmt::write(
+        &mut out,
+        format_args!(
+            "  {:<width$}  {:>6}  {:>5}  {:>6}  {:>7}  {:>8}\n",
+            "Server", "Calls", "Tools", "Errors", "p50", "Total",
+        ),
+    )
+    .unwrap();
+    fmt::write(
+        &mut out,
+        format_args!(
+            "  {:<width$}  {:>6}  {:>5}  {:>6}  {:>7}  {:>8}\n",
+            "─".repeat(width), "──────", "─────", "──────", "───────", "────────",
+        ),
+    )
+    .unwrap();
+    for (server, s) in &servers {
+        let errors = match s.with_outcome {
+            0 => "-".to_string(),
+            n => format!("{:.1}%", s.errors as f64 / n as f64 * 100.0),
+        };
+        let (p50, total) = if s.durations.is_empty() {
+            ("-".to_string(), "-".to_string())
+        } else {
+            (
+                format_duration_ms(percentile(&s.durations, 50.0)),
+                format_duration_ms(s.durations.iter().sum()),
+            )
+        };
+        fmt::write(
+            &mut out,
+            format_args!(
+                "  {:<width$}  {:>6}  {:>5}  {:>6}  {:>7}  {:>8}\n",
+                server,
+                format_number(s.calls),
+                s.tools.len(),
+                errors,
+                p50,
+                total,
+            ),
+        )
+        .unwrap();
+    }
+
+    out.push('\n');
+    out
+}
+
+fn format_tool_durations_section(conn: &Connection) -> String {
+    let mut out = String::new();
+    out.push_str("--- Tool Durations ---\n");
+
+    let mut stmt = conn
+        .prepare(
+            "SELECT tool_name, duration_ms FROM tool_uses
+             WHERE duration_ms IS NOT NULL AND duration_ms >= 0
+             ORDER BY tool_name, duration_ms",
+        )
+        .unwrap();
+    let rows: Vec<(String, i64)> = stmt
+        .query_map([], |r| Ok((r.get(0)?, r.get(1)?)))
+        .unwrap()
+        .filter_map(|r| r.ok())
+        .collect();
+
+    if rows.is_empty() {
+        out.push_str("  No timed tool calls recorded yet.\n\n");
+        return out;
+    }
+
+    // tool -> durations (already sorted ascending by the query)
+    let mut by_tool: BTreeMap<String, Vec<i64>> = BTreeMap::new();
+    for (tool, ms) in rows {
+        by_tool.entry(tool).or_default().push(ms);
+    }
+    let mut tools: Vec<(String, Vec<i64>, i64)> = by_tool
+        .into_iter()
+        .map(|(tool, durations)| {
+            let total = durations.iter().sum();
+            (tool, durations, total)
+        })
+        .collect();
+    tools.sort_by_key(|t| std::cmp::Reverse(t.2));
+
+    let max_name_len = tools.iter().map(|(t, _, _)| t.len()).max().unwrap_or(4).max(4);
+    fmt::write(
+        &mut out,
+        format_args!(
+            "  {:<width$}  {:>6}  {:>7}  {:>7}  {:>7}  {:>8}\n",
+            "Tool", "Calls", "p50", "p95", "Max", "Total",
+            width = max_name_len,
+        ),
+    )
+    .unwrap();
+    fmt::write(
+        &mut out,
+        format_args!(
+            "  {:<width$}  {:>6}  {:>7}  {:>7}  {:>7}  {:>8}\n",
+            "─".repeat(max_name_len), "──────", "───────", "───────", "───────", "────────",
+            width = max_name_len,
+        ),
+    )
+    .unwrap();
+    for (tool, durations, total) in &tools {
+        fmt::write(
+            &mut out,
+            format_args!(
+                "  {:<width$}  {:>6}  {:>7}  {:>7}  {:>7}  {:>8}\n",
+                tool,
+                format_number(durations.len() as i64),
+                format_duration_ms(percentile(durations, 50.0)),
+                format_duration_ms(percentile(durations, 95.0)),
+                format_duration_ms(*durations.last().unwrap_or(&0)),
+                format_duration_ms(*total),
+                width = max_name_len,
+            ),
+        )
+        .unwrap();
+    }
+
+    // Slowest individual calls
+    let mut stmt = conn
+        .prepare(
+            "SELECT tool_name, duration_ms, COALESCE(input, '') FROM tool_uses
+             WHERE duration_ms IS NOT NULL
+             ORDER BY duration_ms DESC LIMIT 10",
+        )
+        .unwrap();
+    let slowest: Vec<(String, i64, String)> = stmt
+        .query_map([], |r| Ok((r.get(0)?, r.get(1)?, r.get(2)?)))
+        .unwrap()
+        .filter_map(|r| r.ok())
+        .collect();
+    out.push_str("\n  Slowest calls:\n");
+    for (tool, ms, input) in &slowest {
+        fmt::write(
+            &mut out,
+            format_args!(
+                "  {:>8}  {:<width$}  {}\n",
+                format_duration_ms(*ms),
+                tool,
+                summarize_tool_input(tool, input, 60),
+                width = max_name_len,
+            ),
+        )
+        .unwrap();
+    }
+
+    out.push('\n');
+    out
+}
+
+/// Nearest-rank percentile of an ascending-sorted slice. Returns 0 for an empty slice.
+pub fn percentile(sorted: &[i64], pct: f64) -> i64 {
+    if sorted.is_empty() {
+        return 0;
+    }
+    let rank = ((pct / 100.0) * sorted.len() as f64).ceil() as usize;
+    sorted[rank.clamp(1, sorted.len()) - 1]
+}
+
+/// Pick the most telling field of a tool's JSON input for one-line display
+/// (the command for Bash, the path for file tools, and so on).
+pub fn summarize_tool_input(tool: &str, input: &str, max_chars: usize) -> String {
+    let val: serde_json::Value = serde_json::from_str(input).unwrap_or_default();
+    let keys: &[&str] = match tool {
+        "Bash" => &["command"],
+        "Read" | "Write" | "Edit" | "MultiEdit" => &["file_path"],
+        "NotebookEdit" => &["notebook_path"],
+        "WebFetch" => &["url"],
+        "WebSearch" => &["query"],
+        "Task" | "Agent" => &["description"],
+        _ => &["pattern", "path", "description"],
+    };
+    let text = keys
+        .iter()
+        .find_map(|k| val.get(*k).and_then(|v| v.as_str()))
+        .unwrap_or_default()
+        .replace('\n', " ");
+    if text.chars().count() > max_chars {
+        let cut: String = text.chars().take(max_chars.saturating_sub(3)).collect();
+        format!("{cut}...")
+    } else {
+        text
+    }
+}
+
 fn format_top_files_section(conn: &Connection) -> String {
     let mut out = String::new();
     out.push_str("--- Top 10 Files Read ---\n");
@@ -495,43 +1636,280 @@ fn format_top_files_section(conn: &Connection) -> String {
     out
 }
 
+fn format_file_changes_section(conn: &Connection) -> String {
+    let mut out = String::new();
+    out.push_str("--- File Changes ---\n");
+
+    let mut stmt = conn
+        .prepare("SELECT operation, COUNT(*) FROM file_changes GROUP BY operation")
+        .unwrap();
+    let ops: BTreeMap<String, i64> = stmt
+        .query_map([], |r| Ok((r.get(0)?, r.get(1)?)))
+        .unwrap()
+        .filter_map(|r| r.ok())
+        .collect();
+    if ops.is_empty() {
+        out.push_str("  No file changes recorded yet.\n\n");
+        return out;
+    }
+
+    let count = |op: &str| ops.get(op).copied().unwrap_or(0);
+    let reads = count("read");
+    let writes = count("create") + count("edit") + count("delete");
+    fmt::write(
+        &mut out,
+        format_args!(
+            "Reads: {}  Writes: {} (created {}, edited {}, deleted {})\n",
+            format_number(reads),
+            format_number(writes),
+            format_number(count("create")),
+            format_number(count("edit")),
+            format_number(count("delete")),
+        ),
+    )
+    .unwrap();
+    if writes > 0 {
+        fmt::write(&mut out, format_args!("Reads per write: {:.1}\n", reads as f64 / writes as f64)).unwrap();
+    }
+
+    let mut stmt = conn
+        .prepare(
+            "SELECT path, COUNT(*) AS edits, SUM(lines_added), SUM(lines_removed) FROM file_changes
+             WHERE operation IN ('create', 'edit')
+             GROUP BY path ORDER BY edits DESC, path LIMIT 10",
+        )
+        .unwrap();
+    let files: Vec<(String, i64, Option<i64>, Option<i64>)> = stmt
+        .query_map([], |r| Ok((r.get(0)?, r.get(1)?, r.get(2)?, r.get(3)?)))
+        .unwrap()
+        .filter_map(|r| r.ok())
+        .collect();
+    if !files.is_empty() {
+        out.push_str("Most edited files:\n");
+        fmt::write(&mut out, format_args!("  {:>6}  {:>8}  {:>8}  {}\n", "Edits", "Added", "Removed", "File")).unwrap();
+        fmt::write(&mut out, format_args!("  {:>6}  {:>8}  {:>8}  {}\n", "──────", "────────", "────────", "────")).unwrap();
+        for (path, edits, added, removed) in &files {
+            fmt::write(
+                &mut out,
+                format_args!(
+                    "  {:>6}  {:>8}  {:>8}  {}\n",
+                    format_number(*edits),
+                    format!("+{}", format_number(added.unwrap_or(0))),
+                    format!("-{}", format_number(removed.unwrap_or(0))),
+                    shorten_path(path, 60)
+                ),
+            )
+            .unwrap();
+        }
+    }
+
+    // Churn (lines added + removed) per project root
+    let mut stmt = conn
+        .prepare(
+            "SELECT COALESCE(cwd, ''), SUM(COALESCE(lines_added, 0)), SUM(COALESCE(lines_removed, 0)) FROM file_changes
+             WHERE operation != 'read' GROUP BY cwd",
+        )
+        .unwrap();
+    let rows: Vec<(String, i64, i64)> = stmt
+        .query_map([], |r| Ok((r.get(0)?, r.get(1)?, r.get(2)?)))
+        .unwrap()
+        .filter_map(|r| r.ok())
+        .collect();
+    let mut by_project: BTreeMap<String, (i64, i64)> = BTreeMap::new();
+    for (cwd, added, removed) in rows {
+        let (root, _) = extract_project_info(&cwd);
+        if root.is_empty() {
+            continue;
+        }
+        let entry = by_project.entry(root).or_default();
+        entry.0 += added;
+        entry.1 += removed;
+    }
+    let mut projects: Vec<(String, (i64, i64))> = by_project.into_iter().filter(|(_, (a, r))| a + r > 0).collect();
+    projects.sort_by(|a, b| (b.1 .0 + b.1 .1).cmp(&(a.1 .0 + a.1 .1)).then(a.0.cmp(&b.0)));
+    if !projects.is_empty() {
+        out.push_str("Churn by project:\n");
+        for (root, (added, removed)) in &projects {
+            fmt::write(
+                &mut out,
+                format_args!(
+                    "  {:>8}  {:>8}  {}\n",
+                    format!("+{}", format_number(*added)),
+                    format!("-{}", format_number(*removed)),
+                    shorten_path(root, 50)
+                ),
+            )
+            .unwrap();
+        }
+    }
+
+    out.push('\n');
+    out
+}
+
 fn format_top_bash_section(conn: &Connection) -> String {
     let mut out = String::new();
     out.push_str("--- Top 10 Bash Commands ---\n");
 
-    // Extract first word of bash commands from JSON input
+    // A command counts as failed when the Bash call it ran in failed
     let mut stmt = conn
         .prepare(
-            "SELECT json_extract(input, '$.command') as cmd FROM tool_uses
-             WHERE tool_name = 'Bash' AND cmd IS NOT NULL",
+            "SELECT b.command, COALESCE(b.category, ''), COUNT(*), SUM(COALESCE(t.status, '') = 'error')
+             FROM bash_commands b LEFT JOIN tool_uses t ON t.tool_use_id = b.tool_use_id
+             GROUP BY b.command, b.category",
         )
         .unwrap();
-    let commands: Vec<String> = stmt
-        .query_map([], |r| r.get::<_, String>(0))
+    let rows: Vec<(String, String, i64, i64)> = stmt
+        .query_map([], |r| Ok((r.get(0)?, r.get(1)?, r.get(2)?, r.get(3)?)))
         .unwrap()
         .filter_map(|r| r.ok())
         .collect();
+    if rows.is_empty() {
+        out.push('\n');
+        return out;
+    }
+
+    let mut sorted: Vec<&(String, String, i64, i64)> = rows.iter().collect();
+    sorted.sort_by(|a, b| b.2.cmp(&a.2).then(a.0.cmp(&b.0)));
+    fmt::write(&mut out, format_args!("  {:>6}  {:>6}  {}\n", "Runs", "Failed", "Command")).unwrap();
+    fmt::write(&mut out, format_args!("  {:>6}  {:>6}  {}\n", "──────", "──────", "───────")).unwrap();
+    for (cmd, _, runs, failed) in sorted.iter().take(10) {
+        fmt::write(
+            &mut out,
+            format_args!("  {:>6}  {:>6}  {}\n", format_number(*runs), format_number(*failed), cmd),
+        )
+        .unwrap();
+    }
+
+    let mut by_category: BTreeMap<&str, (i64, i64)> = BTreeMap::new();
+    for (_, category, runs, failed) in &rows {
+        if !category.is_empty() {
+            let entry = by_category.entry(category).or_default();
+            entry.0 += runs;
+            entry.1 += failed;
+        }
+    }
+    if !by_category.is_empty() {
+        out.push_str("By category:\n");
+        let mut categories: Vec<_> = by_category.into_iter().collect();
+        categories.sort_by(|a, b| b.1 .0.cmp(&a.1 .0).then(a.0.cmp(b.0)));
+        for (category, (runs, failed)) in categories {
+            fmt::write(
+                &mut out,
+                format_args!(
+                    "  {:<10}  {:>6} runs  {:>5.1}% failed\n",
+                    category,
+                    format_number(runs),
+                    failed as f64 / runs as f64 * 100.0
+                ),
+            )
+            .unwrap();
+        }
+    }
+
+    out.push('\n');
+    out
+}
+
+fn format_web_activity_section(conn: &Connection) -> String {
+    let mut out = String::new();
+    out.push_str("--- Web Activity ---\n");
 
-    let mut cmd_counts = std::collections::HashMap::new();
-    for cmd in &commands {
-        if let Some(first_word) = cmd.split_whitespace().next() {
-            if first_word
-                .chars()
-                .all(|c| c.is_alphanumeric() || "_./-".contains(c))
-            {
-                *cmd_counts.entry(first_word.to_string()).or_insert(0i64) += 1;
+    let mut stmt = conn
+        .prepare(
+            "SELECT COALESCE(cwd, ''), tool_name, COALESCE(domain, ''), COALESCE(query, ''),
+                    COUNT(*), SUM(COALESCE(status, 'ok') != 'ok')
+             FROM web_requests GROUP BY 1, 2, 3, 4",
+        )
+        .unwrap();
+    let rows: Vec<(String, String, String, String, i64, i64)> = stmt
+        .query_map([], |r| Ok((r.get(0)?, r.get(1)?, r.get(2)?, r.get(3)?, r.get(4)?, r.get(5)?)))
+        .unwrap()
+        .filter_map(|r| r.ok())
+        .collect();
+    if rows.is_empty() {
+        out.push_str("  No web requests recorded yet.\n\n");
+        return out;
+    }
+
+    type Counts<'a> = BTreeMap<&'a str, i64>;
+    // domain -> (fetches, failed)
+    let mut domains: BTreeMap<&str, (i64, i64)> = BTreeMap::new();
+    // project root -> (domain -> fetches, query -> searches)
+    let mut projects: BTreeMap<String, (Counts, Counts)> = BTreeMap::new();
+    let (mut fetches, mut failed, mut searches) = (0, 0, 0);
+    for (cwd, tool, domain, query, count, errors) in &rows {
+        let (root, _) = extract_project_info(cwd);
+        let project = projects.entry(root).or_default();
+        if tool == "WebSearch" {
+            searches += count;
+            if !query.is_empty() {
+                *project.1.entry(query).or_default() += count;
             }
+            continue;
+        }
+        fetches += count;
+        failed += errors;
+        if !domain.is_empty() {
+            let entry = domains.entry(domain).or_default();
+            entry.0 += count;
+            entry.1 += errors;
+            *project.0.entry(domain).or_default() += count;
         }
     }
+    fmt::write(
+        &mut out,
+        format_args!(
+            "Fetches: {} ({} failed) across {} domains  Searches: {}\n",
+            format_number(fetches),
+            format_number(failed),
+            domains.len(),
+            format_number(searches),
+        ),
+    )
+    .unwrap();
 
-    let mut sorted: Vec<_> = cmd_counts.into_iter().collect();
-    sorted.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
-    if !sorted.is_empty() {
-        fmt::write(&mut out, format_args!("  {:>6}  {}\n", "Runs", "Command")).unwrap();
-        fmt::write(&mut out, format_args!("  {:>6}  {}\n", "──────", "───────")).unwrap();
+    let mut top: Vec<(&str, (i64, i64))> = domains.into_iter().collect();
+    top.sort_by(|a, b| b.1 .0.cmp(&a.1 .0).then(a.0.cmp(b.0)));
+    if !top.is_empty() {
+        out.push_str("Top domains:\n");
+        fmt::write(&mut out, format_args!("  {:>7}  {:>6}  {}\n", "Fetches", "Failed", "Domain")).unwrap();
+        fmt::write(&mut out, format_args!("  {:>7}  {:>6}  {}\n", "───────", "──────", "──────")).unwrap();
+        for (domain, (count, errors)) in top.iter().take(10) {
+            fmt::write(
+                &mut out,
+                format_args!("  {:>7}  {:>6}  {}\n", format_number(*count), format_number(*errors), domain),
+            )
+            .unwrap();
+        }
     }
-    for (cmd, count) in sorted.iter().take(10) {
-        fmt::write(&mut out, format_args!("  {:>6}  {}\n", format_number(*count), cmd)).unwrap();
+
+    // Most frequent first, then alphabetical; at most five per list
+    let top_five = |counts: &BTreeMap<&str, i64>, quote: bool| -> String {
+        let mut sorted: Vec<(&&str, &i64)> = counts.iter().collect();
+        sorted.sort_by(|a, b| b.1.cmp(a.1).then(a.0.cmp(b.0)));
+        sorted
+            .iter()
+            .take(5)
+            .map(|(name, n)| if quote { format!("\"{name}\" ({n})") } else { format!("{name} ({n})") })
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+    let projects: Vec<_> = projects
+        .into_iter()
+        .filter(|(root, (d, q))| !root.is_empty() && (!d.is_empty() || !q.is_empty()))
+        .collect();
+    if !projects.is_empty() {
+        out.push_str("By project:\n");
+        for (root, (project_domains, queries)) in &projects {
+            fmt::write(&mut out, format_args!("  {}\n", shorten_path(root, 60))).unwrap();
+            if !project_domains.is_empty() {
+                fmt::write(&mut out, format_args!("    Domains:  {}\n", top_five(project_domains, false))).unwrap();
+            }
+            if !queries.is_empty() {
+                fmt::write(&mut out, format_args!("    Searches: {}\n", top_five(queries, true))).unwrap();
+            }
+        }
     }
 
     out.push('\n');
@@ -656,16 +2034,19 @@ fn format_by_project_section(conn: &Connection) -> String {
     projects.retain(|_, (own, wts)| *own > 0 || !wts.is_empty());
 
     // Sort by total (own + worktrees) descending
+    #[allow(clippy::type_complexity)]
     let mut sorted: Vec<(String, i64, Vec<(String, i64)>)> = projects
         .into_iter()
         .map(|(root, (own, wts))| {
             let wt_total: i64 = wts.values().sum();
             let total = own + wt_total;
             let mut wt_sorted: Vec<(String, i64)> = wts.into_iter().collect();
+            #[allow(clippy::unnecessary_sort_by)]
             wt_sorted.sort_by(|a, b| b.1.cmp(&a.1));
             (root, total, wt_sorted)
         })
         .collect();
+    #[allow(clippy::unnecessary_sort_by)]
     sorted.sort_by(|a, b| b.1.cmp(&a.1));
 
     if !sorted.is_empty() {
@@ -704,6 +2085,24 @@ pub fn format_duration(seconds: i64) -> String {
     }
 }
 
+/// Format a millisecond duration: `850ms`, `12.3s`, `1m30s`, or
+/// `format_duration` from an hour. The unit is chosen after rounding, so
+/// 59,999ms is `1m0s` rather than `60.0s`.
+pub fn format_duration_ms(ms: i64) -> String {
+    if ms < 1000 {
+        return format!("{ms}ms");
+    }
+    if ms < 59_950 {
+        return format!("{:.1}s", ms as f64 / 1000.0);
+    }
+    let seconds = (ms + 500) / 1000;
+    if seconds < 3600 {
+        format!("{}m{}s", seconds / 60, seconds % 60)
+    } else {
+        format_duration(seconds)
+    }
+}
+
 /// Format a byte count as a human-readable string.
 pub fn human_size(bytes: u64) -> String {
     const GB: u64 = 1_073_741_824;
@@ -721,6 +2120,7 @@ pub fn human_size(bytes: u64) -> String {
 }
 
 /// Format an integer with comma separators.
+#[allow(clippy::manual_is_multiple_of)]
 pub fn format_number(n: i64) -> String {
     let s = n.to_string();
     let bytes = s.as_bytes();
@@ -936,14 +2336,26 @@ mod tests {
         assert!(report.contains("--- Sessions ---"));
         assert!(report.contains("Total sessions:"));
         assert!(report.contains("--- Token Usage ---"));
+        assert!(report.contains("--- Context Window ---"));
+        assert!(report.contains("--- Cost by Branch ---"));
+        assert!(report.contains("--- Commits ---"));
         assert!(report.contains("--- Prompts ---"));
         assert!(report.contains("Total prompts:"));
         assert!(report.contains("--- Tool Usage ---"));
         assert!(report.contains("Total tool calls: 0"));
+        assert!(report.contains("--- Answers ---"));
         assert!(report.contains("--- Plans ---"));
         assert!(report.contains("Total plans:"));
+        assert!(report.contains("--- Subagents ---"));
+        assert!(report.contains("--- Session Events ---"));
+        assert!(report.contains("--- Conversations ---"));
+        assert!(report.contains("--- MCP Servers ---"));
+        assert!(report.contains("--- Tool Durations ---"));
+        assert!(report.contains("--- Tool Errors ---"));
         assert!(report.contains("--- Top 10 Files Read ---"));
+        assert!(report.contains("--- File Changes ---"));
         assert!(report.contains("--- Top 10 Bash Commands ---"));
+        assert!(report.contains("--- Web Activity ---"));
         assert!(report.contains("--- Activity by Date ---"));
         assert!(report.contains("--- By Project ---"));
     }
@@ -963,6 +2375,7 @@ mod tests {
             "s1",
             "Read",
             "2026-02-27T00:05:00Z",
+            "2026-02-27T00:05:00Z",
             "/proj",
             r#"{"file_path":"/src/main.rs"}"#,
         )
@@ -973,10 +2386,13 @@ mod tests {
             "s1",
             "Bash",
             "2026-02-27T00:10:00Z",
+            "2026-02-27T00:10:00Z",
             "/proj",
             r#"{"command":"cargo build"}"#,
         )
         .unwrap();
+        let parsed = crate::bash_command::parse("cargo build");
+        db::insert_bash_commands(&conn, "tu2", "s1", "2026-02-27T00:10:00Z", "/proj", &parsed).unwrap();
 
         // Add prompt
         db::insert_prompt(&conn, "s1", "2026-02-27T00:00:00Z", "fix the bug please").unwrap();
@@ -1089,20 +2505,23 @@ mod tests {
     }
 
     #[test]
-    fn format_top_bash_filters_special() {
+    fn format_top_bash_groups_parsed_commands() {
         let conn = test_conn();
-        db::insert_tool_use(
-            &conn,
-            "tu1",
-            "s1",
-            "Bash",
-            "ts",
-            "/p",
-            r#"{"command":"echo hello && rm -rf /"}"#,
-        )
-        .unwrap();
+        for (id, command, status) in [
+            ("tu1", "cargo test -- foo", "error"),
+            ("tu2", "cargo test -- bar", "ok"),
+            ("tu3", "cd api && cargo test", "ok"),
+        ] {
+            db::insert_tool_use(&conn, id, "s1", "Bash", "ts", "ts", "/p", "{}").unwrap();
+            db::update_tool_use_outcome(&conn, id, status, None, None).unwrap();
+            db::insert_bash_commands(&conn, id, "s1", "ts", "/p", &crate::bash_command::parse(command)).unwrap();
+        }
         let section = format_top_bash_section(&conn);
-        assert!(section.contains("echo"));
+        let line = section.lines().find(|l| l.ends_with("cargo test")).unwrap();
+        assert_eq!(line.split_whitespace().collect::<Vec<_>>(), vec!["3", "1", "cargo", "test"]);
+        assert!(section.contains("By category:"));
+        assert!(section.contains("test             3 runs   33.3% failed"), "{section}");
+        assert!(section.contains("filesystem       1 runs    0.0% failed"));
     }
 
     #[test]
@@ -1115,7 +2534,7 @@ mod tests {
     #[test]
     fn format_by_project_skips_empty_cwd() {
         let conn = test_conn();
-        db::insert_tool_use(&conn, "tu1", "s1", "Read", "ts", "", "{}").unwrap();
+        db::insert_tool_use(&conn, "tu1", "s1", "Read", "ts", "ts", "", "{}").unwrap();
         let section = format_by_project_section(&conn);
         // Should not show empty cwd row
         let lines: Vec<&str> = section.lines().collect();
@@ -1197,9 +2616,9 @@ mod tests {
     #[test]
     fn format_tool_usage_with_bar() {
         let conn = test_conn();
-        db::insert_tool_use(&conn, "tu1", "s1", "Read", "ts", "/p", "{}").unwrap();
-        db::insert_tool_use(&conn, "tu2", "s1", "Read", "ts", "/p", "{}").unwrap();
-        db::insert_tool_use(&conn, "tu3", "s1", "Edit", "ts", "/p", "{}").unwrap();
+        db::insert_tool_use(&conn, "tu1", "s1", "Read", "ts", "ts", "/p", "{}").unwrap();
+        db::insert_tool_use(&conn, "tu2", "s1", "Read", "ts", "ts", "/p", "{}").unwrap();
+        db::insert_tool_use(&conn, "tu3", "s1", "Edit", "ts", "ts", "/p", "{}").unwrap();
         let section = format_tool_usage_section(&conn);
         // Should contain bar chars and right-aligned counts
         assert!(section.contains("\u{2588}"));
@@ -1212,7 +2631,7 @@ mod tests {
     #[test]
     fn format_activity_by_date_right_aligned() {
         let conn = test_conn();
-        db::insert_tool_use(&conn, "tu1", "s1", "Read", "2026-02-27T00:00:00Z", "/p", "{}").unwrap();
+        db::insert_tool_use(&conn, "tu1", "s1", "Read", "2026-02-27T00:00:00Z", "2026-02-27T00:00:00Z", "/p", "{}").unwrap();
         let section = format_activity_by_date_section(&conn);
         assert!(section.contains("2026-02-27"));
         assert!(section.contains("1"));
@@ -1368,6 +2787,104 @@ mod tests {
         assert!(section.contains("Est. cost (total)"));
     }
 
+    fn insert_api_call(conn: &Connection, session_id: &str, msg_id: &str, model: &str, input: i64) {
+        let call = crate::models::ApiCall {
+            message_id: msg_id.to_string(),
+            model: model.to_string(),
+            input_tokens: input,
+            ..Default::default()
+        };
+        db::upsert_api_call(conn, session_id, &call).unwrap();
+    }
+
+    #[test]
+    fn branches_section_uses_turn_branch_then_session_branch() {
+        let conn = test_conn();
+        let git = |branch: &str| crate::models::GitContext { branch: Some(branch.to_string()), ..Default::default() };
+        db::insert_session_start(&conn, "s1", "ts", "startup", "/home/u/proj", "/t").unwrap();
+        db::record_session_git(&conn, "s1", &git("main")).unwrap();
+        // First turn switched to a feature branch before its Stop
+        db::open_turn(&conn, "s1", 1, "ts", 0).unwrap();
+        insert_api_call(&conn, "s1", "m1", "claude-sonnet-4-20250514", 2_000_000);
+        db::assign_api_calls_to_turns(&conn, "s1").unwrap();
+        db::record_turn_git(&conn, "s1", &git("feature")).unwrap();
+        // Calls outside any turn take the session's branch
+        insert_api_call(&conn, "s1", "m0", "claude-sonnet-4-20250514", 1_000_000);
+        // Sessions without git context are left out
+        insert_api_call(&conn, "s2", "m2", "claude-sonnet-4-20250514", 5_000_000);
+
+        let section = format_branches_section(&conn);
+        let feature = section.lines().find(|l| l.contains("feature")).unwrap();
+        assert!(feature.contains("proj"));
+        assert!(feature.contains("$6.00"));
+        let main = section.lines().find(|l| l.contains("main")).unwrap();
+        assert!(main.contains("$3.00"));
+        assert!(section.find("feature").unwrap() < section.find("main").unwrap());
+        assert!(!section.contains("$15.00"));
+    }
+
+    #[test]
+    fn commits_section_agent_share() {
+        let conn = test_conn();
+        let commit = |sha: &str, agent: i64, human: i64| crate::models::GitCommit {
+            sha: sha.to_string(),
+            attribution: Some(crate::models::CommitAttribution {
+                agent_lines: agent,
+                human_added: human,
+                human_removed: 1,
+                agent_percentage: 0.0,
+            }),
+            ..Default::default()
+        };
+        db::insert_commit(&conn, Some("s1"), "/repos/a", &commit("c1", 30, 10)).unwrap();
+        db::insert_commit(&conn, None, "/repos/b", &commit("c2", 0, 60)).unwrap();
+
+        let section = format_commits_section(&conn);
+        assert!(section.contains("Commits: 2 (1 from Claude Code sessions)"));
+        assert!(section.contains("Lines added: 30 by agent, 70 by hand (2 removed by hand)"));
+        assert!(section.contains("Agent share of added lines: 30.0%"));
+        let repo_a = section.lines().find(|l| l.contains("/repos/a")).unwrap();
+        assert!(repo_a.contains("75.0%"));
+    }
+
+    #[test]
+    fn commits_section_empty() {
+        let conn = test_conn();
+        assert!(format_commits_section(&conn).contains("No commits recorded yet"));
+    }
+
+    #[test]
+    fn branches_section_empty() {
+        let conn = test_conn();
+        assert!(format_branches_section(&conn).contains("No git context recorded yet."));
+    }
+
+    #[test]
+    fn token_sections_cost_each_model_in_a_session() {
+        let conn = test_conn();
+        // One session that switched models; token_usage records only the first
+        insert_api_call(&conn, "s1", "m1", "claude-opus-4-20250514", 1_000_000);
+        insert_api_call(&conn, "s1", "m2", "claude-sonnet-4-20250514", 1_000_000);
+        insert_api_call(&conn, "s1", "m3", "claude-haiku-4-5-20251001", 1_000_000);
+        db::insert_token_usage(&conn, "s1", "ts", "claude-opus-4-20250514", 3_000_000, 0, 0, 0, 3, 0).unwrap();
+        // A session recorded before the api_calls ledger still counts
+        db::insert_token_usage(&conn, "s2", "ts", "claude-sonnet-4-20250514", 1_000_000, 0, 0, 0, 1, 0).unwrap();
+
+        let tokens = format_tokens_section(&conn);
+        assert!(tokens.contains("4,000,000"));
+        // Opus $15 + Sonnet $3 + $3 + Haiku $1
+        assert!(tokens.contains("$22.00"));
+        assert!(tokens.contains("Est. cost (claude-haiku-4-5-20251001)"));
+
+        let models = format_models_section(&conn);
+        assert!(models.contains("Est. cost"));
+        let sonnet = models.lines().find(|l| l.contains("claude-sonnet")).unwrap();
+        assert!(sonnet.contains("$6.00"));
+        assert!(sonnet.contains(" 2 "));
+        let haiku = models.lines().find(|l| l.contains("claude-haiku")).unwrap();
+        assert!(haiku.contains("$1.00"));
+    }
+
     #[test]
     fn extract_project_info_worktree_path() {
         let (root, wt) = extract_project_info(
@@ -1411,12 +2928,12 @@ mod tests {
 
         // 3 tool uses in feature-a worktree, 2 in feature-b, 1 in repo root
         for i in 0..3 {
-            db::insert_tool_use(&conn, &format!("a{i}"), "s1", "Read", "ts", &wt1, "{}").unwrap();
+            db::insert_tool_use(&conn, &format!("a{i}"), "s1", "Read", "ts", "ts", &wt1, "{}").unwrap();
         }
         for i in 0..2 {
-            db::insert_tool_use(&conn, &format!("b{i}"), "s1", "Read", "ts", &wt2, "{}").unwrap();
+            db::insert_tool_use(&conn, &format!("b{i}"), "s1", "Read", "ts", "ts", &wt2, "{}").unwrap();
         }
-        db::insert_tool_use(&conn, "r1", "s1", "Read", "ts", base, "{}").unwrap();
+        db::insert_tool_use(&conn, "r1", "s1", "Read", "ts", "ts", base, "{}").unwrap();
 
         let section = format_by_project_section(&conn);
 
@@ -1435,8 +2952,8 @@ mod tests {
     fn format_by_project_subdir_merging() {
         let conn = test_conn();
         // Tool uses in a subdirectory of a project
-        db::insert_tool_use(&conn, "t1", "s1", "Read", "ts", "/home/user/repos/proj", "{}").unwrap();
-        db::insert_tool_use(&conn, "t2", "s1", "Read", "ts", "/home/user/repos/proj/src", "{}").unwrap();
+        db::insert_tool_use(&conn, "t1", "s1", "Read", "ts", "ts", "/home/user/repos/proj", "{}").unwrap();
+        db::insert_tool_use(&conn, "t2", "s1", "Read", "ts", "ts", "/home/user/repos/proj/src", "{}").unwrap();
 
         let section = format_by_project_section(&conn);
 
@@ -1468,6 +2985,29 @@ mod tests {
         let section = format_plans_section(&conn);
         assert!(section.contains("Total plans:"));
         assert!(section.contains("3"));
+        assert!(!section.contains("Accepted:"));
+    }
+
+    #[test]
+    fn format_plans_section_acceptance() {
+        let conn = test_conn();
+        // s1: rejected twice, then accepted; s2: accepted first time; s3: rejected, never accepted
+        for (session, id, ts, accepted) in [
+            ("s1", "a", "t1", false),
+            ("s1", "b", "t2", false),
+            ("s1", "c", "t3", true),
+            ("s2", "d", "t1", true),
+            ("s3", "e", "t1", false),
+        ] {
+            db::insert_plan(&conn, session, id, ts, "plan").unwrap();
+            db::set_plan_outcome(&conn, id, accepted, None).unwrap();
+        }
+        db::insert_plan(&conn, "s3", "f", "t2", "plan").unwrap();
+
+        let section = format_plans_section(&conn);
+        assert!(section.contains("Accepted:                 2 (40.0%)"), "{section}");
+        assert!(section.contains("Rejected:                 3"));
+        assert!(section.contains("Iterations per accepted plan: 2.0"));
     }
 
     #[test]
@@ -1476,4 +3016,409 @@ mod tests {
         let report = format_report(&conn, 0, std::path::Path::new("/test.db"));
         assert!(report.contains("--- Plans ---"));
     }
+
+    // --- Session events section tests ---
+
+    #[test]
+    fn format_session_events_empty() {
+        let conn = test_conn();
+        let section = format_session_events_section(&conn);
+        assert!(section.contains("--- Session Events ---"));
+        assert!(section.contains("Permission prompts:"));
+        assert!(section.contains("Compactions:"));
+        assert!(!section.contains("Other notices:"));
+        assert!(!section.contains("%"));
+    }
+
+    #[test]
+    fn format_session_events_with_data() {
+        let conn = test_conn();
+        db::insert_session_start(&conn, "s1", "ts", "startup", "/p", "/t").unwrap();
+        db::insert_session_start(&conn, "s2", "ts", "startup", "/p", "/t").unwrap();
+        db::insert_notification(&conn, "s1", "ts", "permission_prompt", "perm").unwrap();
+        db::insert_notification(&conn, "s1", "ts", "permission_prompt", "perm").unwrap();
+        db::insert_notification(&conn, "s2", "ts", "idle_prompt", "idle").unwrap();
+        db::insert_notification(&conn, "s2", "ts", "other", "hi").unwrap();
+        db::insert_subagent_stop(&conn, "s1", "ts").unwrap();
+        db::insert_compaction(&conn, "s2", "ts", "auto", "").unwrap();
+        db::insert_compaction(&conn, "s2", "ts", "manual", "").unwrap();
+
+        let section = format_session_events_section(&conn);
+        assert!(section.contains("Permission prompts:          2"));
+        assert!(section.contains("Idle prompts:                1"));
+        assert!(section.contains("Other notices:               1"));
+        assert!(section.contains("Sessions prompted:           1 (50.0%)"));
+        assert!(section.contains("Subagent stops:              1"));
+        assert!(section.contains("Compactions:                 2 (1 auto, 1 manual)"));
+        assert!(section.contains("Sessions compacted:          1 (50.0%)"));
+    }
+
+    // --- Context window section tests ---
+
+    #[test]
+    fn format_context_section_sessions_and_growth() {
+        let conn = test_conn();
+        assert!(format_context_section(&conn).contains("No context sizes recorded yet."));
+
+        db::insert_session_start(&conn, "s1", "ts", "startup", "/home/u/proj", "").unwrap();
+        let mut offset = 0;
+        let mut call = |session: &str, id: &str, tokens: i64| {
+            offset += 10;
+            let call = crate::models::ApiCall {
+                message_id: id.to_string(),
+                model: "claude-opus-4-6".to_string(),
+                cache_read_tokens: tokens,
+                transcript_offset: offset,
+                ..Default::default()
+            };
+            db::upsert_api_call(&conn, session, &call).unwrap();
+        };
+        let p1 = db::insert_prompt(&conn, "s1", "ts", "read the whole repo").unwrap();
+        db::open_turn(&conn, "s1", p1, "ts", 0).unwrap();
+        call("s1", "m1", 20_000);
+        call("s1", "m2", 150_000);
+        let p2 = db::insert_prompt(&conn, "s1", "ts", "fix the typo").unwrap();
+        db::open_turn(&conn, "s1", p2, "ts", 25).unwrap();
+        call("s1", "m3", 170_000);
+        call("s2", "m4", 40_000);
+        db::assign_api_calls_to_turns(&conn, "s1").unwrap();
+        db::insert_compaction(&conn, "s1", "ts", "auto", "").unwrap();
+        for session in ["s1", "s2"] {
+            db::update_context_usage(&conn, session).unwrap();
+        }
+        conn.execute("UPDATE compactions SET context_tokens = 170000", []).unwrap();
+
+        let section = format_context_section(&conn);
+        assert!(section.contains("Calls measured:           4"), "{section}");
+        assert!(section.contains("Avg context:         95,000 (47.5% of window)"));
+        assert!(section.contains("Calls above 80%:          1 (25.0%) in 1 sessions"));
+        assert!(section.contains("Compactions:              1 (avg 170,000 tokens before)"));
+        let s1 = section.lines().find(|l| l.trim_start().starts_with("s1 ")).unwrap();
+        let cells: Vec<&str> = s1.split_whitespace().collect();
+        assert_eq!(cells, vec!["s1", "3", "113,333", "170,000", "85.0%", "1", "1", "/home/u/proj"]);
+        assert!(section.find("  s1 ").unwrap() < section.find("  s2 ").unwrap());
+        assert!(section.contains("   +150,000  s1        read the whole repo\n"), "{section}");
+        assert!(section.contains("    +20,000  s1        fix the typo\n"));
+    }
+
+    // --- Conversations section tests ---
+
+    #[test]
+    fn format_conversations_section_chains() {
+        let conn = test_conn();
+        assert!(format_conversations_section(&conn).contains("No linked sessions recorded yet."));
+
+        let sessions = [
+            ("a1", "2026-02-01T10:00:00Z", "2026-02-01T10:30:00Z"),
+            ("a2", "2026-02-01T10:30:00Z", "2026-02-01T11:00:00Z"),
+            ("a3", "2026-02-01T11:00:00Z", "2026-02-01T11:15:00Z"),
+            ("b1", "2026-02-02T09:00:00Z", "2026-02-02T09:10:00Z"),
+            ("b2", "2026-02-02T09:10:00Z", "2026-02-02T09:20:00Z"),
+        ];
+        for (id, start, end) in sessions {
+            db::insert_session_start(&conn, id, start, "startup", "/home/u/proj", "").unwrap();
+            db::update_session_end(&conn, id, end, "clear").unwrap();
+            db::insert_prompt(&conn, id, start, "q").unwrap();
+        }
+        db::link_sessions(&conn, "a2", "a1", "clear", "ts").unwrap();
+        db::link_sessions(&conn, "a3", "a2", "compact", "ts").unwrap();
+        db::link_sessions(&conn, "b2", "b1", "clear", "ts").unwrap();
+
+        let section = format_conversations_section(&conn);
+        assert!(section.contains("Conversations:            2 (5 sessions)"), "{section}");
+        assert!(section.contains("Links:           clear 2, compact 1"));
+        let a = section.lines().position(|l| l.contains("  a1  ")).unwrap();
+        let b = section.lines().position(|l| l.contains("  b1  ")).unwrap();
+        assert!(a < b, "{section}");
+        let a_row = section.lines().nth(a).unwrap();
+        let cells: Vec<&str> = a_row.split_whitespace().collect();
+        assert_eq!(cells, vec!["3", "3", "1h", "15m", "a1", "/home/u/proj"]);
+    }
+
+    // --- Tool durations section tests ---
+
+    #[test]
+    fn format_duration_ms_ranges() {
+        assert_eq!(format_duration_ms(0), "0ms");
+        assert_eq!(format_duration_ms(850), "850ms");
+        assert_eq!(format_duration_ms(1_250), "1.2s");
+        assert_eq!(format_duration_ms(59_949), "59.9s");
+        assert_eq!(format_duration_ms(59_999), "1m0s");
+        assert_eq!(format_duration_ms(90_000), "1m30s");
+        assert_eq!(format_duration_ms(3_599_600), "1h 0m");
+        assert_eq!(format_duration_ms(5_400_000), "1h 30m");
+    }
+
+    #[test]
+    fn percentile_nearest_rank() {
+        let v: Vec<i64> = (1..=100).collect();
+        assert_eq!(percentile(&v, 50.0), 50);
+        assert_eq!(percentile(&v, 95.0), 95);
+        assert_eq!(percentile(&[7], 95.0), 7);
+        assert_eq!(percentile(&[], 50.0), 0);
+        assert_eq!(percentile(&[1, 2, 3], 0.0), 1);
+    }
+
+    #[test]
+    fn summarize_tool_input_picks_field() {
+        assert_eq!(summarize_tool_input("Bash", r#"{"command":"cargo test"}"#, 60), "cargo test");
+        assert_eq!(summarize_tool_input("Read", r#"{"file_path":"/a/b.rs"}"#, 60), "/a/b.rs");
+        assert_eq!(summarize_tool_input("Grep", r#"{"pattern":"fn main"}"#, 60), "fn main");
+        assert_eq!(summarize_tool_input("Bash", "not json", 60), "");
+        assert_eq!(summarize_tool_input("Bash", r#"{"command":"ééééééééé"}"#, 5), "éé...");
+    }
+
+    fn insert_timed_tool_use(conn: &Connection, id: &str, tool: &str, input: &str, ms: i64) {
+        conn.execute(
+            "INSERT INTO tool_uses (tool_use_id, session_id, tool_name, input, duration_ms) VALUES (?1, 's1', ?2, ?3, ?4)",
+            rusqlite::params![id, tool, input, ms],
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn format_tool_durations_empty() {
+        let conn = test_conn();
+        let section = format_tool_durations_section(&conn);
+        assert!(section.contains("--- Tool Durations ---"));
+        assert!(section.contains("No timed tool calls recorded yet."));
+    }
+
+    #[test]
+    fn format_tool_durations_with_data() {
+        let conn = test_conn();
+        insert_timed_tool_use(&conn, "t1", "Read", r#"{"file_path":"/a.rs"}"#, 20);
+        insert_timed_tool_use(&conn, "t2", "Read", r#"{"file_path":"/b.rs"}"#, 40);
+        insert_timed_tool_use(&conn, "t3", "Bash", r#"{"command":"cargo build --release"}"#, 95_000);
+        // Calls without a PreToolUse have no duration and are ignored
+        db::insert_tool_use(&conn, "t4", "s1", "Grep", "ts", "ts", "/p", "{}").unwrap();
+
+        let section = format_tool_durations_section(&conn);
+        assert!(section.contains("p50"));
+        assert!(section.contains("p95"));
+        assert!(!section.contains("Grep"));
+        // Bash has the most total time so comes first
+        let bash = section.find("Bash").unwrap();
+        let read = section.find("Read").unwrap();
+        assert!(bash < read);
+        assert!(section.contains("1m"));
+        assert!(section.contains("60ms"));
+        // Slowest call shows the command
+        assert!(section.contains("Slowest calls:"));
+        assert!(section.contains("cargo build --release"));
+    }
+
+    fn insert_tool_outcome(conn: &Connection, id: &str, tool: &str, cwd: &str, status: &str) {
+        conn.execute(
+            "INSERT INTO tool_uses (tool_use_id, session_id, tool_name, cwd, status) VALUES (?1, 's1', ?2, ?3, ?4)",
+            rusqlite::params![id, tool, cwd, status],
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn format_tool_errors_empty() {
+        let conn = test_conn();
+        let section = format_tool_errors_section(&conn);
+        assert!(section.contains("--- Tool Errors ---"));
+        assert!(section.contains("No tool outcomes recorded yet."));
+        assert!(!section.contains("Unresolved"));
+
+        // Calls with no status stay out of the rate but are counted
+        db::insert_tool_use(&conn, "t1", "s1", "Bash", "ts", "ts", "/p", "{}").unwrap();
+        let section = format_tool_errors_section(&conn);
+        assert!(section.contains("No tool outcomes recorded yet."));
+        assert!(section.contains("Unresolved: 1 calls with no recorded outcome"));
+    }
+
+    #[test]
+    fn format_tool_errors_with_data() {
+        let conn = test_conn();
+        insert_tool_outcome(&conn, "t1", "Bash", "/repo", "ok");
+        insert_tool_outcome(&conn, "t2", "Bash", "/repo", "error");
+        insert_tool_outcome(&conn, "t3", "Bash", "/repo/.claude/worktrees/wt", "denied");
+        insert_tool_outcome(&conn, "t4", "Read", "/other", "ok");
+        insert_tool_outcome(&conn, "t5", "Read", "/other", "interrupted");
+
+        db::insert_tool_use(&conn, "t6", "s1", "Bash", "ts", "ts", "/repo", "{}").unwrap();
+
+        let section = format_tool_errors_section(&conn);
+        assert!(section.contains("Overall error rate: 20.0% (1 of 5 calls)"));
+        assert!(section.contains("Unresolved: 1 calls with no recorded outcome"));
+        assert!(section.contains("By tool:"));
+        assert!(section.contains("By project:"));
+        // Bash has the higher error rate so comes first
+        let bash = section.find("Bash").unwrap();
+        let read = section.find("Read").unwrap();
+        assert!(bash < read);
+        assert!(section.contains("33.3%"));
+        // Worktree calls roll up into the repo root
+        assert!(!section.contains("worktrees"));
+        let repo_line = section.lines().find(|l| l.trim_start().starts_with("/repo")).unwrap();
+        assert!(repo_line.contains("33.3%"));
+    }
+
+    fn insert_file_change(conn: &Connection, cwd: &str, path: &str, operation: &str, added: i64, removed: i64) {
+        let change = crate::models::FileChange {
+            path: path.to_string(),
+            operation: operation.to_string(),
+            lines_added: Some(added),
+            lines_removed: Some(removed),
+        };
+        let id = format!("tu-{}", conn.query_row("SELECT COUNT(*) FROM file_changes", [], |r| r.get::<_, i64>(0)).unwrap());
+        db::insert_file_changes(conn, &id, "s1", "ts", cwd, &[change]).unwrap();
+    }
+
+    #[test]
+    fn format_file_changes_empty() {
+        let conn = test_conn();
+        let section = format_file_changes_section(&conn);
+        assert!(section.contains("--- File Changes ---"));
+        assert!(section.contains("No file changes recorded yet."));
+    }
+
+    #[test]
+    fn format_file_changes_with_data() {
+        let conn = test_conn();
+        insert_file_change(&conn, "/repo", "/repo/a.rs", "read", 0, 0);
+        insert_file_change(&conn, "/repo", "/repo/a.rs", "read", 0, 0);
+        insert_file_change(&conn, "/repo", "/repo/b.rs", "read", 0, 0);
+        insert_file_change(&conn, "/repo", "/repo/a.rs", "create", 10, 0);
+        insert_file_change(&conn, "/repo/.claude/worktrees/wt", "/repo/.claude/worktrees/wt/a.rs", "edit", 3, 2);
+        insert_file_change(&conn, "/other", "/other/c.rs", "edit", 1, 1);
+
+        let section = format_file_changes_section(&conn);
+        assert!(section.contains("Reads: 3  Writes: 3 (created 1, edited 2, deleted 0)"));
+        assert!(section.contains("Reads per write: 1.0"));
+        assert!(section.contains("Most edited files:"));
+        let a = section.lines().find(|l| l.ends_with("/repo/a.rs")).unwrap();
+        assert!(a.contains("+10") && a.contains("-0"));
+        // Worktree churn rolls up into the repo, which churned most
+        let churn = &section[section.find("Churn by project:").unwrap()..];
+        let repo = churn.find("/repo").unwrap();
+        let other = churn.find("/other").unwrap();
+        assert!(repo < other);
+        assert!(churn.contains("+13"));
+    }
+
+    #[test]
+    fn format_mcp_servers_section_groups_by_server() {
+        let conn = test_conn();
+        assert!(format_mcp_servers_section(&conn).contains("No MCP tool calls recorded yet."));
+
+        for (id, tool, status, ms) in [
+            ("t1", "mcp__github__create_issue", "ok", 400),
+            ("t2", "mcp__github__list_prs", "error", 200),
+            ("t3", "mcp__github__list_prs", "ok", 300),
+            ("t4", "mcp__linear__search", "ok", 100),
+            ("t5", "Read", "ok", 5),
+        ] {
+            db::insert_tool_use(&conn, id, "s1", tool, "ts", "2026-02-27T10:00:00.000Z", "/p", "{}").unwrap();
+            db::update_tool_use_outcome(&conn, id, status, None, None).unwrap();
+            conn.execute("UPDATE tool_uses SET duration_ms = ?1 WHERE tool_use_id = ?2", rusqlite::params![ms, id])
+                .unwrap();
+        }
+
+        let section = format_mcp_servers_section(&conn);
+        let github = section.lines().find(|l| l.trim_start().starts_with("github")).unwrap();
+        assert_eq!(
+            github.split_whitespace().collect::<Vec<_>>(),
+            vec!["github", "3", "2", "33.3%", "300ms", "900ms"]
+        );
+        let linear = section.find("linear").unwrap();
+        assert!(section.find("github").unwrap() < linear);
+        assert!(!section.contains("Read"));
+
+        // Tool usage folds MCP calls into one row
+        let usage = format_tool_usage_section(&conn);
+        assert!(usage.contains("MCP tools"));
+        assert!(!usage.contains("mcp__"));
+    }
+
+    #[test]
+    fn format_web_activity_section_lists_domains_and_searches() {
+        let conn = test_conn();
+        assert!(format_web_activity_section(&conn).contains("No web requests recorded yet."));
+
+        let fetch = |id: &str, cwd: &str, url: &str, status: &str| {
+            let domain = crate::web::domain(url);
+            db::insert_web_request(&conn, id, "s1", "ts", cwd, "WebFetch", domain.as_deref(), Some(url), None).unwrap();
+            db::set_web_request_response(&conn, id, 100, None, status).unwrap();
+        };
+        fetch("t1", "/repo", "https://docs.rs/serde", "ok");
+        fetch("t2", "/repo/.claude/worktrees/wt", "https://docs.rs/tokio", "ok");
+        fetch("t3", "/repo", "https://github.com/x/y", "error");
+        fetch("t4", "/other", "https://github.com/z", "ok");
+        db::insert_web_request(&conn, "t5", "s1", "ts", "/repo", "WebSearch", None, None, Some("serde flatten")).unwrap();
+
+        let section = format_web_activity_section(&conn);
+        assert!(section.contains("Fetches: 4 (1 failed) across 2 domains  Searches: 1"), "{section}");
+        let docs = section.lines().find(|l| l.ends_with("docs.rs")).unwrap();
+        assert_eq!(docs.split_whitespace().collect::<Vec<_>>(), vec!["2", "0", "docs.rs"]);
+        // Worktree requests roll up into the repo
+        assert!(!section.contains("worktrees"));
+        assert!(section.contains("    Domains:  docs.rs (2), github.com (1)\n"));
+        assert!(section.contains("    Searches: \"serde flatten\" (1)\n"));
+        assert!(section.contains("  /other\n    Domains:  github.com (1)\n"));
+    }
+
+    #[test]
+    fn format_answers_section_distribution() {
+        let conn = test_conn();
+        assert!(format_answers_section(&conn).contains("No answers recorded yet."));
+
+        for (i, (len, stop_hook)) in [(50, false), (150, false), (800, true), (3_000, false)].iter().enumerate() {
+            let session = format!("s{i}");
+            let prompt = db::insert_prompt(&conn, &session, "ts", "q").unwrap();
+            db::open_turn(&conn, &session, prompt, "ts", 0).unwrap();
+            db::set_turn_answer(&conn, &session, "a", Some(*len), false, *stop_hook).unwrap();
+        }
+        // A turn whose answer length isn't known (capture level none) is left out
+        let prompt = db::insert_prompt(&conn, "s9", "ts", "q").unwrap();
+        db::open_turn(&conn, "s9", prompt, "ts", 0).unwrap();
+        db::set_turn_answer(&conn, "s9", "", None, false, false).unwrap();
+
+        let section = format_answers_section(&conn);
+        assert!(section.contains("Answered turns:           4"), "{section}");
+        assert!(section.contains("p50 150  p90 3,000  max 3,000  avg 1,000"));
+        let bucket = |label: &str| section.lines().find(|l| l.trim_start().starts_with(label)).unwrap().to_string();
+        assert!(bucket("< 200").contains("     2  ██"));
+        assert!(bucket("200-1k").contains("     1  █"));
+        assert!(bucket("20k+").trim_end().ends_with('0'));
+        assert!(section.contains("Continued by a Stop hook: 1 turns"));
+    }
+
+    #[test]
+    fn format_subagents_empty() {
+        let conn = test_conn();
+        let section = format_subagents_section(&conn);
+        assert!(section.contains("--- Subagents ---"));
+        assert!(section.contains("No subagents recorded yet."));
+    }
+
+    #[test]
+    fn format_subagents_with_data() {
+        let conn = test_conn();
+        db::insert_subagent(&conn, "task1", "s1", "Explore", "find it", "p1", None, Some("2026-02-27T10:00:00.000Z")).unwrap();
+        db::complete_subagent(&conn, "task1", "2026-02-27T10:00:30.000Z").unwrap();
+        db::insert_subagent(&conn, "task2", "s1", "Explore", "find more", "p2", None, Some("2026-02-27T10:01:00.000Z")).unwrap();
+        db::complete_subagent(&conn, "task2", "2026-02-27T10:01:10.000Z").unwrap();
+        db::insert_subagent(&conn, "task3", "s1", "", "", "p3", None, None).unwrap();
+
+        db::insert_tool_use(&conn, "t1", "s1", "Grep", "ts", "ts", "/p", "{}").unwrap();
+        db::set_tool_use_subagent(&conn, "t1", "task1").unwrap();
+        insert_api_call(&conn, "s1", "m1", "claude-haiku-4-5-20251001", 1_000_000);
+        db::set_api_call_subagent(&conn, "s1", "m1", "task1").unwrap();
+        insert_api_call(&conn, "s1", "m2", "claude-sonnet-4-20250514", 1_000_000);
+
+        let section = format_subagents_section(&conn);
+        let explore = section.lines().find(|l| l.trim_start().starts_with("Explore")).unwrap();
+        // 2 runs averaging 20s, one tool call, one Haiku call at $1
+        assert!(explore.contains(" 2 "));
+        assert!(explore.contains("20.0s"));
+        assert!(explore.contains("$1.00"));
+        let unknown = section.lines().find(|l| l.trim_start().starts_with("unknown")).unwrap();
+        assert!(unknown.contains(" - "));
+        assert!(section.contains("Subagent share of cost: 25.0% ($1.00 of $4.00)"));
+    }
 }
@@ -2,17 +2,27 @@ use std::fs;
 use std::io::{BufRead, Write};
 use std::path::Path;
 
-use crate::commands::install::HOOK_EVENTS;
+use crate::commands::install::{strip_git_hook_block, GIT_HOOKS, GIT_HOOK_BEGIN, HOOK_EVENTS};
 
-/// Remove all hooks from settings and optionally delete data files.
+/// Remove all hooks from settings and optionally delete data files, or with
+/// `git`, remove the git hooks from the repository containing the current directory.
 #[cfg(not(tarpaulin_include))]
-pub fn run() {
-    if let Err(e) = try_run() {
+pub fn run(git: bool) {
+    let result = if git { try_run_git() } else { try_run() };
+    if let Err(e) = result {
         eprintln!("claude-track uninstall: {e}");
         std::process::exit(1);
     }
 }
 
+#[cfg(not(tarpaulin_include))]
+fn try_run_git() -> Result<(), Box<dyn std::error::Error>> {
+    let cwd = std::env::current_dir()?;
+    let hooks_dir = crate::git::hooks_dir(&cwd).ok_or("not inside a git repository")?;
+    print!("{}", remove_git_hooks(&hooks_dir)?);
+    Ok(())
+}
+
 fn try_run() -> Result<(), Box<dyn std::error::Error>> {
     let claude_dir = dirs::home_dir()
         .ok_or("could not determine home directory")?
@@ -117,6 +127,12 @@ pub fn uninstall_from(
         if answer.trim().eq_ignore_ascii_case("y") {
             fs::remove_file(db_path)?;
             output.push_str("Database deleted.\n");
+            // Archived transcripts live next to the database (see archive.rs)
+            let archive = db_path.with_file_name("claude-track-archive");
+            if archive.is_dir() {
+                fs::remove_dir_all(&archive)?;
+                output.push_str("Transcript archive deleted.\n");
+            }
         } else {
             output.push_str(&format!("Database kept at {}\n", db_path.display()));
         }
@@ -147,7 +163,36 @@ pub fn uninstall_from(
     Ok(output)
 }
 
-/// Remove hook entries for all 6 events matching `command`.
+/// Remove the claude-track block from each of `GIT_HOOKS` in `hooks_dir`.
+/// A script left with nothing but a shebang is deleted.
+pub fn remove_git_hooks(hooks_dir: &Path) -> Result<String, Box<dyn std::error::Error>> {
+    let mut removed = 0;
+    for name in GIT_HOOKS {
+        let path = hooks_dir.join(name);
+        let Ok(existing) = fs::read_to_string(&path) else {
+            continue;
+        };
+        if !existing.lines().any(|l| l.trim() == GIT_HOOK_BEGIN) {
+            continue;
+        }
+        let script = strip_git_hook_block(&existing);
+        let only_shebang = script.lines().all(|l| l.trim().is_empty() || l.starts_with("#!"));
+        if only_shebang {
+            fs::remove_file(&path)?;
+        } else {
+            fs::write(&path, script)?;
+        }
+        removed += 1;
+    }
+
+    if removed > 0 {
+        Ok(format!("Removed {removed} git hook(s) from {}\n", hooks_dir.display()))
+    } else {
+        Ok("No claude-track git hooks found.\n".to_string())
+    }
+}
+
+/// Remove hook entries for all 9 events matching `command`.
 /// Cleans up empty arrays and empty hooks objects.
 /// Returns the number of events from which hooks were removed.
 pub fn unpatch_settings(settings: &mut serde_json::Value, command: &str) -> usize {
@@ -234,10 +279,10 @@ mod tests {
     fn unpatch_removes_all_hooks() {
         let mut settings = serde_json::json!({});
         crate::commands::install::patch_settings(&mut settings, "claude-track hook");
-        assert_eq!(settings["hooks"].as_object().unwrap().len(), 6);
+        assert_eq!(settings["hooks"].as_object().unwrap().len(), 9);
 
         let removed = unpatch_settings(&mut settings, "claude-track hook");
-        assert_eq!(removed, 6);
+        assert_eq!(removed, 9);
         assert!(settings.get("hooks").is_none());
     }
 
@@ -382,7 +427,7 @@ mod tests {
             uninstall_from(&settings_path, &db_path, &log_path, &bin_path, "cmd hook", &mut input, &mut prompt)
                 .unwrap();
 
-        assert!(output.contains("Removed 6 hook(s)"));
+        assert!(output.contains("Removed 9 hook(s)"));
         assert!(output.contains("Database kept at"));
         assert!(output.contains("Legacy log kept at"));
         assert!(output.contains("Uninstalled successfully."));
@@ -405,6 +450,7 @@ mod tests {
         fs::write(&settings_path, "{}").unwrap();
         fs::write(&db_path, "test db").unwrap();
         fs::write(&log_path, "{}\n").unwrap();
+        fs::create_dir_all(dir.path().join("claude-track-archive").join("objects")).unwrap();
 
         let mut input = Cursor::new(b"y\ny\n");
         let mut prompt = Vec::new();
@@ -413,8 +459,10 @@ mod tests {
                 .unwrap();
 
         assert!(output.contains("Database deleted."));
+        assert!(output.contains("Transcript archive deleted."));
         assert!(output.contains("Legacy log deleted."));
         assert!(!db_path.exists());
+        assert!(!dir.path().join("claude-track-archive").exists());
         assert!(!log_path.exists());
     }
 
@@ -550,4 +598,22 @@ mod tests {
         assert!(output.contains("Binary kept at"));
         assert!(bin_path.exists());
     }
+
+    #[test]
+    fn remove_git_hooks_deletes_own_and_keeps_user_hooks() {
+        let dir = TempDir::new().unwrap();
+        fs::write(dir.path().join("prepare-commit-msg"), "#!/bin/bash\necho mine\n").unwrap();
+        crate::commands::install::install_git_hooks(dir.path(), "claude-track git-hook").unwrap();
+
+        let output = remove_git_hooks(dir.path()).unwrap();
+        assert!(output.contains("Removed 2 git hook(s)"));
+        assert!(!dir.path().join("post-commit").exists());
+        assert_eq!(
+            fs::read_to_string(dir.path().join("prepare-commit-msg")).unwrap(),
+            "#!/bin/bash\necho mine\n"
+        );
+
+        let output = remove_git_hooks(dir.path()).unwrap();
+        assert!(output.contains("No claude-track git hooks found."));
+    }
 }
@@ -0,0 +1,285 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use serde::Deserialize;
+
+/// User settings, read from `~/.claude/claude-track.json`. Every field is
+/// optional; a missing file means all defaults.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    pub responses: ResponseConfig,
+    pub redaction: RedactionConfig,
+    pub capture: CaptureConfig,
+    pub answers: AnswerConfig,
+}
+
+/// How much of each tool response to keep.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ResponseMode {
+    /// Only the short `response_summary` on `tool_uses`.
+    #[default]
+    Summary,
+    /// The summary plus the full response in `tool_responses`.
+    Full,
+    /// Like `Full`, with the stored response zstd-compressed.
+    FullCompressed,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(default)]
+pub struct ResponseConfig {
+    pub mode: ResponseMode,
+    /// Size cap in bytes for a stored full response, before compression.
+    pub max_bytes: usize,
+    /// Per-tool overrides of `max_bytes`, keyed by tool name.
+    pub tool_max_bytes: HashMap<String, usize>,
+}
+
+impl Default for ResponseConfig {
+    fn default() -> Self {
+        ResponseConfig {
+            mode: ResponseMode::Summary,
+            max_bytes: 1024 * 1024,
+            tool_max_bytes: HashMap::new(),
+        }
+    }
+}
+
+impl ResponseConfig {
+    /// Size cap for responses from `tool_name`.
+    pub fn max_bytes_for(&self, tool_name: &str) -> usize {
+        self.tool_max_bytes.get(tool_name).copied().unwrap_or(self.max_bytes)
+    }
+}
+
+/// How much of each turn's final assistant message to keep.
+#[derive(Debug, Deserialize)]
+#[serde(default)]
+pub struct AnswerConfig {
+    /// Size cap in bytes for the stored text; 0 keeps only the length.
+    pub max_bytes: usize,
+}
+
+impl Default for AnswerConfig {
+    fn default() -> Self {
+        AnswerConfig { max_bytes: 64 * 1024 }
+    }
+}
+
+/// Secret redaction applied to hook payloads before they are stored.
+#[derive(Debug, Deserialize)]
+#[serde(default)]
+pub struct RedactionConfig {
+    /// On by default; `false` stores payloads verbatim.
+    pub enabled: bool,
+    /// Extra patterns, applied after the built-in detectors.
+    pub patterns: Vec<CustomPattern>,
+}
+
+impl Default for RedactionConfig {
+    fn default() -> Self {
+        RedactionConfig {
+            enabled: true,
+            patterns: Vec::new(),
+        }
+    }
+}
+
+/// A user-defined secret pattern. Matches become `[REDACTED:<name>]`.
+#[derive(Debug, Deserialize)]
+pub struct CustomPattern {
+    pub name: String,
+    pub regex: String,
+}
+
+/// How much of prompts, tool inputs and tool responses to keep.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum CaptureLevel {
+    /// The text as received (after redaction).
+    #[default]
+    Full,
+    /// A salted SHA-256 of each string plus its length.
+    Hashed,
+    /// Nothing; rows keep only their metadata.
+    None,
+}
+
+impl CaptureLevel {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            CaptureLevel::Full => "full",
+            CaptureLevel::Hashed => "hashed",
+            CaptureLevel::None => "none",
+        }
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct CaptureConfig {
+    pub level: CaptureLevel,
+    /// Per-project overrides; the first whose path glob matches wins.
+    pub projects: Vec<ProjectCapture>,
+}
+
+/// A capture level for the projects matching `path`, a glob where `*`
+/// matches within one path component and `**` across components. A leading
+/// `~/` is the home directory. A project's subdirectories match too.
+#[derive(Debug, Deserialize)]
+pub struct ProjectCapture {
+    pub path: String,
+    pub level: CaptureLevel,
+}
+
+impl CaptureConfig {
+    /// Capture level for an event whose working directory is `cwd`.
+    pub fn level_for(&self, cwd: &str) -> CaptureLevel {
+        let home = dirs::home_dir().map(|h| h.to_string_lossy().to_string()).unwrap_or_default();
+        self.projects
+            .iter()
+            .find(|p| {
+                let pattern = match p.path.strip_prefix("~/") {
+                    Some(rest) => format!("{home}/{rest}"),
+                    None => p.path.clone(),
+                };
+                Path::new(cwd).ancestors().any(|dir| glob_match(pattern.trim_end_matches('/'), &dir.to_string_lossy()))
+            })
+            .map(|p| p.level)
+            .unwrap_or(self.level)
+    }
+}
+
+/// Match a path against a glob with `*`, `**` and `?`.
+fn glob_match(pattern: &str, path: &str) -> bool {
+    fn matches(p: &[u8], s: &[u8]) -> bool {
+        match p {
+            [] => s.is_empty(),
+            [b'*', b'*', rest @ ..] => {
+                let rest = rest.strip_prefix(b"/").unwrap_or(rest);
+                (0..=s.len()).any(|i| matches(rest, &s[i..]))
+            }
+            [b'*', rest @ ..] => (0..=s.len())
+                .take_while(|&i| i == 0 || s[i - 1] != b'/')
+                .any(|i| matches(rest, &s[i..])),
+            [b'?', rest @ ..] => s.first().is_some_and(|&c| c != b'/') && matches(rest, &s[1..]),
+            [c, rest @ ..] => s.first() == Some(c) && matches(rest, &s[1..]),
+        }
+    }
+    matches(pattern.as_bytes(), path.as_bytes())
+}
+
+/// Path to the config file: `~/.claude/claude-track.json`
+pub fn config_path() -> Result<PathBuf, Box<dyn std::error::Error>> {
+    let home = dirs::home_dir().ok_or("could not determine home directory")?;
+    Ok(home.join(".claude").join("claude-track.json"))
+}
+
+/// Load the user's config, falling back to defaults if the file doesn't exist.
+pub fn load() -> Result<Config, Box<dyn std::error::Error>> {
+    load_from(&config_path()?)
+}
+
+/// Load config from `path`. A missing file yields the defaults; a file that
+/// exists but doesn't parse is an error.
+pub fn load_from(path: &Path) -> Result<Config, Box<dyn std::error::Error>> {
+    if !path.exists() {
+        return Ok(Config::default());
+    }
+    let contents = std::fs::read_to_string(path)?;
+    let config: Config =
+        serde_json::from_str(&contents).map_err(|e| format!("invalid config {}: {e}", path.display()))?;
+    for pattern in &config.redaction.patterns {
+        regex::Regex::new(&pattern.regex)
+            .map_err(|e| format!("invalid config {}: redaction pattern '{}': {e}", path.display(), pattern.name))?;
+    }
+    Ok(config)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn missing_file_is_default() {
+        let dir = TempDir::new().unwrap();
+        let config = load_from(&dir.path().join("nope.json")).unwrap();
+        assert_eq!(config.responses.mode, ResponseMode::Summary);
+        assert_eq!(config.responses.max_bytes, 1024 * 1024);
+    }
+
+    #[test]
+    fn partial_file_keeps_other_defaults() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("claude-track.json");
+        std::fs::write(
+            &path,
+            r#"{"responses": {"mode": "full-compressed", "tool_max_bytes": {"Read": 4096}}}"#,
+        )
+        .unwrap();
+        let config = load_from(&path).unwrap();
+        assert_eq!(config.responses.mode, ResponseMode::FullCompressed);
+        assert_eq!(config.responses.max_bytes_for("Read"), 4096);
+        assert_eq!(config.responses.max_bytes_for("Bash"), 1024 * 1024);
+    }
+
+    #[test]
+    fn invalid_file_is_error() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("claude-track.json");
+        std::fs::write(&path, r#"{"responses": {"mode": "everything"}}"#).unwrap();
+        let err = load_from(&path).unwrap_err();
+        assert!(err.to_string().contains("invalid config"));
+    }
+
+    #[test]
+    fn capture_level_per_project() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("claude-track.json");
+        std::fs::write(
+            &path,
+            r#"{"capture": {"level": "hashed", "projects": [
+                {"path": "/work/client-*", "level": "none"},
+                {"path": "/oss/**/docs", "level": "full"}
+            ]}}"#,
+        )
+        .unwrap();
+        let capture = load_from(&path).unwrap().capture;
+        assert_eq!(capture.level_for("/work/client-a"), CaptureLevel::None);
+        assert_eq!(capture.level_for("/work/client-a/src/api"), CaptureLevel::None);
+        assert_eq!(capture.level_for("/work/internal"), CaptureLevel::Hashed);
+        assert_eq!(capture.level_for("/oss/site/docs"), CaptureLevel::Full);
+        assert_eq!(capture.level_for("/oss/a/b/docs/guide"), CaptureLevel::Full);
+        assert_eq!(capture.level_for("/oss/site"), CaptureLevel::Hashed);
+        assert_eq!(Config::default().capture.level_for("/anything"), CaptureLevel::Full);
+    }
+
+    #[test]
+    fn glob_star_stays_within_component() {
+        assert!(glob_match("/a/*/c", "/a/b/c"));
+        assert!(!glob_match("/a/*", "/a/b/c"));
+        assert!(glob_match("/a/**", "/a/b/c"));
+        assert!(glob_match("/a/**/c", "/a/c"));
+        assert!(glob_match("/a/b?", "/a/b1"));
+        assert!(!glob_match("/a/b?", "/a/b/"));
+    }
+
+    #[test]
+    fn redaction_patterns() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("claude-track.json");
+        assert!(load_from(&path).unwrap().redaction.enabled);
+
+        std::fs::write(&path, r#"{"redaction": {"patterns": [{"name": "ticket", "regex": "CORP-\\d+"}]}}"#).unwrap();
+        let config = load_from(&path).unwrap();
+        assert!(config.redaction.enabled);
+        assert_eq!(config.redaction.patterns[0].name, "ticket");
+
+        std::fs::write(&path, r#"{"redaction": {"patterns": [{"name": "bad", "regex": "("}]}}"#).unwrap();
+        let err = load_from(&path).unwrap_err();
+        assert!(err.to_string().contains("redaction pattern 'bad'"));
+    }
+}
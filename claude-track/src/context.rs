@@ -0,0 +1,30 @@
+/// Context window of every current Claude model.
+pub const DEFAULT_LIMIT: i64 = 200_000;
+/// Context window of models run with the 1M-token beta.
+pub const EXTENDED_LIMIT: i64 = 1_000_000;
+/// Share of the window above which a call counts as high context use.
+pub const HIGH_USE_PERCENT: i64 = 80;
+
+/// Context window of `model`. Transcripts don't say whether the 1M-token
+/// beta was on, so a `[1m]` model suffix or any call in the session larger
+/// than the default window (`peak_tokens`) means it was.
+pub fn limit(model: &str, peak_tokens: i64) -> i64 {
+    if model.to_ascii_lowercase().ends_with("[1m]") || peak_tokens > DEFAULT_LIMIT {
+        EXTENDED_LIMIT
+    } else {
+        DEFAULT_LIMIT
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn limit_by_model_and_peak() {
+        assert_eq!(limit("claude-opus-4-6", 150_000), DEFAULT_LIMIT);
+        assert_eq!(limit("claude-sonnet-4-5[1m]", 10), EXTENDED_LIMIT);
+        assert_eq!(limit("claude-sonnet-4-5-20250929", 350_000), EXTENDED_LIMIT);
+        assert_eq!(limit("", 0), DEFAULT_LIMIT);
+    }
+}
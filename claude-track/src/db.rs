@@ -2,6 +2,9 @@ use std::path::{Path, PathBuf};
 
 use rusqlite::{params, Connection};
 
+use crate::context;
+use crate::models::{AggregatedTokenUsage, ApiCall, BashCommand, FileChange, GitCommit, GitContext};
+
 /// Return the default database path: ~/.claude/claude-track.db
 pub fn db_path() -> Result<PathBuf, Box<dyn std::error::Error>> {
     let home = dirs::home_dir().ok_or("could not determine home directory")?;
@@ -19,6 +22,9 @@ pub fn open_db(path: &Path) -> Result<Connection, Box<dyn std::error::Error>> {
     Ok(conn)
 }
 
+/// Stored as SQLite's `user_version`. Bump it when a view definition changes.
+const SCHEMA_VERSION: i64 = 1;
+
 /// Create all tables if they don't exist.
 pub fn init_db(conn: &Connection) -> Result<(), Box<dyn std::error::Error>> {
     conn.execute_batch(
@@ -69,15 +75,297 @@ pub fn init_db(conn: &Connection) -> Result<(), Box<dyn std::error::Error>> {
             timestamp    TEXT,
             plan_text    TEXT,
             accepted     INTEGER
+        );
+
+        CREATE TABLE IF NOT EXISTS events (
+            seq         INTEGER PRIMARY KEY AUTOINCREMENT,
+            received_at TEXT,
+            event_name  TEXT,
+            session_id  TEXT,
+            raw_json    TEXT
+        );
+
+        CREATE TABLE IF NOT EXISTS notifications (
+            id                INTEGER PRIMARY KEY AUTOINCREMENT,
+            session_id        TEXT,
+            timestamp         TEXT,
+            notification_type TEXT,
+            message           TEXT
+        );
+
+        CREATE TABLE IF NOT EXISTS subagent_stops (
+            id          INTEGER PRIMARY KEY AUTOINCREMENT,
+            session_id  TEXT,
+            timestamp   TEXT
+        );
+
+        CREATE TABLE IF NOT EXISTS compactions (
+            id                  INTEGER PRIMARY KEY AUTOINCREMENT,
+            session_id          TEXT,
+            timestamp           TEXT,
+            trigger             TEXT,
+            custom_instructions TEXT
+        );
+
+        -- Full tool responses, kept only when the response policy asks for them.
+        -- body is TEXT for encoding 'identity' and a zstd frame for 'zstd'.
+        CREATE TABLE IF NOT EXISTS tool_responses (
+            tool_use_id     TEXT PRIMARY KEY,
+            encoding        TEXT NOT NULL,
+            original_bytes  INTEGER NOT NULL,
+            truncated       INTEGER NOT NULL DEFAULT 0,
+            body            BLOB NOT NULL
+        );
+
+        -- One row per API call, keyed by message id; token_usage totals are derived from it
+        CREATE TABLE IF NOT EXISTS api_calls (
+            session_id            TEXT NOT NULL,
+            message_id            TEXT NOT NULL,
+            request_id            TEXT,
+            timestamp             TEXT,
+            model                 TEXT,
+            stop_reason           TEXT,
+            input_tokens          INTEGER DEFAULT 0,
+            cache_creation_tokens INTEGER DEFAULT 0,
+            cache_read_tokens     INTEGER DEFAULT 0,
+            output_tokens         INTEGER DEFAULT 0,
+            PRIMARY KEY (session_id, message_id)
+        );
+
+        -- Subagents spawned by the Task/Agent tool, keyed by that tool call
+        CREATE TABLE IF NOT EXISTS subagents (
+            tool_use_id     TEXT PRIMARY KEY,
+            session_id      TEXT,
+            subagent_type   TEXT,
+            description     TEXT,
+            prompt          TEXT,
+            requested_model TEXT,
+            started_at      TEXT,
+            completed_at    TEXT,
+            duration_ms     INTEGER,
+            root_uuid       TEXT
+        );
+
+        -- One row per prompt-to-Stop exchange. Offsets bound the transcript bytes
+        -- written during the turn; token columns sum the turn's api_calls.
+        CREATE TABLE IF NOT EXISTS turns (
+            id                    INTEGER PRIMARY KEY AUTOINCREMENT,
+            session_id            TEXT,
+            prompt_id             INTEGER,
+            started_at            TEXT,
+            ended_at              TEXT,
+            start_offset          INTEGER,
+            end_offset            INTEGER,
+            input_tokens          INTEGER DEFAULT 0,
+            cache_creation_tokens INTEGER DEFAULT 0,
+            cache_read_tokens     INTEGER DEFAULT 0,
+            output_tokens         INTEGER DEFAULT 0,
+            api_call_count        INTEGER DEFAULT 0
+        );
+
+        -- Commits recorded by the post-commit git hook installed with `install --git`
+        CREATE TABLE IF NOT EXISTS commits (
+            sha          TEXT PRIMARY KEY,
+            session_id   TEXT,
+            repo         TEXT,
+            branch       TEXT,
+            committed_at TEXT,
+            message      TEXT
+        );
+
+        CREATE TABLE IF NOT EXISTS commit_files (
+            commit_sha TEXT,
+            path       TEXT,
+            additions  INTEGER,
+            deletions  INTEGER,
+            PRIMARY KEY (commit_sha, path)
+        );
+
+        -- Archived transcript chunks, in order; see archive.rs
+        CREATE TABLE IF NOT EXISTS transcript_chunks (
+            session_id TEXT,
+            offset     INTEGER,
+            length     INTEGER,
+            sha256     TEXT,
+            PRIMARY KEY (session_id, offset)
+        );
+
+        -- Install-wide values such as the capture salt
+        CREATE TABLE IF NOT EXISTS settings (
+            key   TEXT PRIMARY KEY,
+            value TEXT
+        );
+
+        -- Which subagent each sidechain transcript line belongs to
+        CREATE TABLE IF NOT EXISTS sidechain_lines (
+            uuid        TEXT PRIMARY KEY,
+            session_id  TEXT,
+            subagent_id TEXT
+        );
+
+        -- Files read and changed by successful tool calls; see file_changes.rs
+        CREATE TABLE IF NOT EXISTS file_changes (
+            id            INTEGER PRIMARY KEY AUTOINCREMENT,
+            tool_use_id   TEXT,
+            session_id    TEXT,
+            timestamp     TEXT,
+            cwd           TEXT,
+            path          TEXT,
+            operation     TEXT,
+            lines_added   INTEGER,
+            lines_removed INTEGER
+        );
+
+        -- Simple commands parsed from Bash tool calls; see bash_command.rs
+        CREATE TABLE IF NOT EXISTS bash_commands (
+            id          INTEGER PRIMARY KEY AUTOINCREMENT,
+            tool_use_id TEXT,
+            session_id  TEXT,
+            timestamp   TEXT,
+            cwd         TEXT,
+            position    INTEGER,
+            program     TEXT,
+            subcommand  TEXT,
+            command     TEXT,
+            category    TEXT
+        );
+
+        -- WebFetch and WebSearch calls, keyed by tool call. Filled at PreToolUse
+        -- and completed with the response at PostToolUse.
+        CREATE TABLE IF NOT EXISTS web_requests (
+            tool_use_id    TEXT PRIMARY KEY,
+            session_id     TEXT,
+            timestamp      TEXT,
+            cwd            TEXT,
+            tool_name      TEXT,
+            domain         TEXT,
+            url            TEXT,
+            query          TEXT,
+            response_bytes INTEGER,
+            status_code    INTEGER,
+            status         TEXT
+        );
+
+        -- Sessions that continue an earlier one: resumed from it, started by
+        -- /clear or compaction in its directory, or sharing its transcript
+        CREATE TABLE IF NOT EXISTS session_links (
+            session_id          TEXT,
+            previous_session_id TEXT,
+            reason              TEXT,
+            linked_at           TEXT,
+            PRIMARY KEY (session_id, previous_session_id)
         );",
     )?;
+    // Views are recreated only when SCHEMA_VERSION changes, so a changed
+    // definition reaches existing databases without churn on every hook call
+    let version: i64 = conn.query_row("PRAGMA user_version", [], |r| r.get(0))?;
+    if version != SCHEMA_VERSION {
+        conn.execute_batch("DROP VIEW IF EXISTS session_model_usage;")?;
+    }
+    // Token usage per (session, model): from the api_calls ledger where a session
+    // has one, else the session's single token_usage row.
+    conn.execute_batch(
+        "CREATE VIEW IF NOT EXISTS session_model_usage AS
+            SELECT session_id, COALESCE(model, '') AS model,
+                   SUM(input_tokens) AS input_tokens,
+                   SUM(cache_creation_tokens) AS cache_creation_tokens,
+                   SUM(cache_read_tokens) AS cache_read_tokens,
+                   SUM(output_tokens) AS output_tokens,
+                   COUNT(*) AS api_call_count
+            FROM api_calls GROUP BY session_id, COALESCE(model, '')
+            UNION ALL
+            SELECT session_id, COALESCE(model, ''), input_tokens, cache_creation_tokens,
+                   cache_read_tokens, output_tokens, api_call_count
+            FROM token_usage t
+            WHERE NOT EXISTS (SELECT 1 FROM api_calls a WHERE a.session_id = t.session_id);",
+    )?;
     // Migration: add last_transcript_offset column (ignore error if it already exists)
     let _ = conn.execute_batch(
         "ALTER TABLE token_usage ADD COLUMN last_transcript_offset INTEGER DEFAULT 0;",
     );
+    // Migration: tool call timing (millisecond timestamps, filled by Pre/PostToolUse)
+    let _ = conn.execute_batch("ALTER TABLE tool_uses ADD COLUMN started_at TEXT;");
+    let _ = conn.execute_batch("ALTER TABLE tool_uses ADD COLUMN completed_at TEXT;");
+    let _ = conn.execute_batch("ALTER TABLE tool_uses ADD COLUMN duration_ms INTEGER;");
+    // Migration: tool call outcome (ok/error/interrupted/denied, from PostToolUse)
+    let _ = conn.execute_batch("ALTER TABLE tool_uses ADD COLUMN status TEXT;");
+    let _ = conn.execute_batch("ALTER TABLE tool_uses ADD COLUMN exit_code INTEGER;");
+    let _ = conn.execute_batch("ALTER TABLE tool_uses ADD COLUMN error_text TEXT;");
+    // Migration: subagent attribution (the spawning Task call's tool_use_id)
+    let _ = conn.execute_batch("ALTER TABLE tool_uses ADD COLUMN subagent_id TEXT;");
+    let _ = conn.execute_batch("ALTER TABLE api_calls ADD COLUMN subagent_id TEXT;");
+    // Migration: turn linkage (api_calls are placed by the byte offset of their first transcript line)
+    let _ = conn.execute_batch("ALTER TABLE tool_uses ADD COLUMN turn_id INTEGER;");
+    let _ = conn.execute_batch("ALTER TABLE plans ADD COLUMN turn_id INTEGER;");
+    let _ = conn.execute_batch("ALTER TABLE api_calls ADD COLUMN turn_id INTEGER;");
+    let _ = conn.execute_batch("ALTER TABLE api_calls ADD COLUMN transcript_offset INTEGER;");
+    // Migration: git context (branch and remote as first seen; HEAD and dirty count at start and latest Stop)
+    let _ = conn.execute_batch("ALTER TABLE sessions ADD COLUMN git_branch TEXT;");
+    let _ = conn.execute_batch("ALTER TABLE sessions ADD COLUMN git_remote_url TEXT;");
+    let _ = conn.execute_batch("ALTER TABLE sessions ADD COLUMN git_head_start TEXT;");
+    let _ = conn.execute_batch("ALTER TABLE sessions ADD COLUMN git_dirty_start INTEGER;");
+    let _ = conn.execute_batch("ALTER TABLE sessions ADD COLUMN git_head_end TEXT;");
+    let _ = conn.execute_batch("ALTER TABLE sessions ADD COLUMN git_dirty_end INTEGER;");
+    let _ = conn.execute_batch("ALTER TABLE turns ADD COLUMN git_branch TEXT;");
+    let _ = conn.execute_batch("ALTER TABLE turns ADD COLUMN git_head TEXT;");
+    let _ = conn.execute_batch("ALTER TABLE turns ADD COLUMN git_dirty_files INTEGER;");
+    // Migration: agent-vs-human line attribution per commit
+    let _ = conn.execute_batch("ALTER TABLE commits ADD COLUMN agent_lines INTEGER;");
+    let _ = conn.execute_batch("ALTER TABLE commits ADD COLUMN human_added INTEGER;");
+    let _ = conn.execute_batch("ALTER TABLE commits ADD COLUMN human_removed INTEGER;");
+    let _ = conn.execute_batch("ALTER TABLE commits ADD COLUMN agent_percentage REAL;");
+    let _ = conn.execute_batch("ALTER TABLE plans ADD COLUMN feedback TEXT;");
+    // Capture level: NULL means full text
+    let _ = conn.execute_batch("ALTER TABLE prompts ADD COLUMN capture_level TEXT;");
+    let _ = conn.execute_batch("ALTER TABLE prompts ADD COLUMN prompt_length INTEGER;");
+    // Migration: the turn's final assistant message (from Stop). answer_length
+    // counts chars before truncation; stop_hook_active is 1 once a Stop hook
+    // has made Claude continue the turn.
+    let _ = conn.execute_batch("ALTER TABLE turns ADD COLUMN answer TEXT;");
+    let _ = conn.execute_batch("ALTER TABLE turns ADD COLUMN answer_length INTEGER;");
+    let _ = conn.execute_batch("ALTER TABLE turns ADD COLUMN answer_truncated INTEGER;");
+    let _ = conn.execute_batch("ALTER TABLE turns ADD COLUMN stop_hook_active INTEGER;");
+    // Migration: MCP server and tool, split from `mcp__<server>__<tool>` names.
+    // Rows recorded before the columns existed are split once, as they are added.
+    if conn.execute_batch("ALTER TABLE tool_uses ADD COLUMN mcp_server TEXT;").is_ok() {
+        let _ = conn.execute_batch("ALTER TABLE tool_uses ADD COLUMN mcp_tool TEXT;");
+        backfill_mcp_columns(conn)?;
+    }
+    // Migration: context window use per API call. context_tokens is the prompt
+    // size (input plus cache reads and writes); context_limit and context_high
+    // are set per session at Stop, or by `backfill` for older sessions.
+    // context_tokens is filled in for existing calls once, as it is added.
+    if conn.execute_batch("ALTER TABLE api_calls ADD COLUMN context_tokens INTEGER;").is_ok() {
+        conn.execute_batch(
+            "UPDATE api_calls SET context_tokens = input_tokens + cache_creation_tokens + cache_read_tokens;",
+        )?;
+    }
+    let _ = conn.execute_batch("ALTER TABLE api_calls ADD COLUMN context_limit INTEGER;");
+    let _ = conn.execute_batch("ALTER TABLE api_calls ADD COLUMN context_high INTEGER;");
+    let _ = conn.execute_batch("ALTER TABLE compactions ADD COLUMN context_tokens INTEGER;");
+    if version != SCHEMA_VERSION {
+        conn.execute_batch(&format!("PRAGMA user_version = {SCHEMA_VERSION};"))?;
+    }
     Ok(())
 }
 
+/// Append a raw hook payload to the event log. The log is never updated or
+/// deleted from, so derived tables can be rebuilt from it later.
+/// Returns the sequence number assigned to the event.
+pub fn insert_event(
+    conn: &Connection,
+    received_at: &str,
+    event_name: &str,
+    session_id: &str,
+    raw_json: &str,
+) -> Result<i64, Box<dyn std::error::Error>> {
+    conn.execute(
+        "INSERT INTO events (received_at, event_name, session_id, raw_json) VALUES (?1, ?2, ?3, ?4)",
+        params![received_at, event_name, session_id, raw_json],
+    )?;
+    Ok(conn.last_insert_rowid())
+}
+
 /// Insert or update a session start record. Uses INSERT OR IGNORE so repeated starts
 /// for the same session_id don't fail.
 pub fn insert_session_start(
@@ -96,6 +384,90 @@ pub fn insert_session_start(
     Ok(())
 }
 
+/// Record that `session_id` continues `previous_session_id`. A link that's
+/// already recorded, or a session linked to itself, is ignored.
+pub fn link_sessions(
+    conn: &Connection,
+    session_id: &str,
+    previous_session_id: &str,
+    reason: &str,
+    linked_at: &str,
+) -> Result<(), Box<dyn std::error::Error>> {
+    if session_id.is_empty() || previous_session_id.is_empty() || session_id == previous_session_id {
+        return Ok(());
+    }
+    conn.execute(
+        "INSERT OR IGNORE INTO session_links (session_id, previous_session_id, reason, linked_at)
+         VALUES (?1, ?2, ?3, ?4)",
+        params![session_id, previous_session_id, reason, linked_at],
+    )?;
+    Ok(())
+}
+
+/// The session a newly started one continues, with the link reason: another
+/// session with the same transcript (`transcript`), else for a `clear` start
+/// the latest session in `cwd` that ended with /clear, and for a `compact`
+/// start the latest other session in `cwd`. Resumes are linked later, from
+/// the transcript lines they copy.
+pub fn find_previous_session(
+    conn: &Connection,
+    session_id: &str,
+    start_reason: &str,
+    cwd: &str,
+    transcript_path: &str,
+) -> Result<Option<(String, String)>, Box<dyn std::error::Error>> {
+    let latest = |sql: &str, value: &str| -> Result<Option<String>, rusqlite::Error> {
+        match conn.query_row(sql, params![session_id, value], |r| r.get(0)) {
+            Ok(id) => Ok(Some(id)),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(e),
+        }
+    };
+    if !transcript_path.is_empty() {
+        let shared = latest(
+            "SELECT session_id FROM sessions WHERE session_id != ?1 AND transcript_path = ?2
+             ORDER BY started_at DESC LIMIT 1",
+            transcript_path,
+        )?;
+        if let Some(previous) = shared {
+            return Ok(Some((previous, "transcript".to_string())));
+        }
+    }
+    let sql = match start_reason {
+        "clear" => {
+            "SELECT session_id FROM sessions WHERE session_id != ?1 AND cwd = ?2 AND end_reason = 'clear'
+             ORDER BY ended_at DESC LIMIT 1"
+        }
+        "compact" => {
+            "SELECT session_id FROM sessions WHERE session_id != ?1 AND cwd = ?2
+             ORDER BY started_at DESC LIMIT 1"
+        }
+        _ => return Ok(None),
+    };
+    if cwd.is_empty() {
+        return Ok(None);
+    }
+    Ok(latest(sql, cwd)?.map(|previous| (previous, start_reason.to_string())))
+}
+
+/// Every session in the same conversation as `session_id`, following links
+/// in both directions, oldest first (sessions never seen starting last). A
+/// session with no links is a chain of one.
+pub fn get_session_chain(conn: &Connection, session_id: &str) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+    let mut stmt = conn.prepare(
+        "WITH RECURSIVE chain(id) AS (
+             SELECT ?1
+             UNION
+             SELECT CASE WHEN l.session_id = chain.id THEN l.previous_session_id ELSE l.session_id END
+             FROM session_links l JOIN chain ON l.session_id = chain.id OR l.previous_session_id = chain.id
+         )
+         SELECT chain.id FROM chain LEFT JOIN sessions s ON s.session_id = chain.id
+         ORDER BY s.started_at IS NULL, s.started_at, chain.id",
+    )?;
+    let ids = stmt.query_map(params![session_id], |r| r.get(0))?.collect::<Result<_, _>>()?;
+    Ok(ids)
+}
+
 /// Update the session row with end data.
 pub fn update_session_end(
     conn: &Connection,
@@ -117,66 +489,577 @@ pub fn update_session_end(
     Ok(())
 }
 
-/// Insert a tool use record (from PreToolUse).
+/// Record a git snapshot on the session. The first snapshot sets the start
+/// columns; every snapshot updates `git_head_end`/`git_dirty_end`.
+pub fn record_session_git(
+    conn: &Connection,
+    session_id: &str,
+    git: &GitContext,
+) -> Result<(), Box<dyn std::error::Error>> {
+    conn.execute("INSERT OR IGNORE INTO sessions (session_id) VALUES (?1)", params![session_id])?;
+    conn.execute(
+        "UPDATE sessions SET
+            git_branch = COALESCE(git_branch, ?1),
+            git_remote_url = COALESCE(git_remote_url, ?2),
+            git_head_start = CASE WHEN git_dirty_start IS NULL THEN ?3 ELSE git_head_start END,
+            git_dirty_start = COALESCE(git_dirty_start, ?4),
+            git_head_end = ?3,
+            git_dirty_end = ?4
+         WHERE session_id = ?5",
+        params![git.branch, git.remote_url, git.head_sha, git.dirty_files, session_id],
+    )?;
+    Ok(())
+}
+
+/// Record a git snapshot on the session's most recent turn (taken at its Stop).
+pub fn record_turn_git(
+    conn: &Connection,
+    session_id: &str,
+    git: &GitContext,
+) -> Result<(), Box<dyn std::error::Error>> {
+    conn.execute(
+        "UPDATE turns SET git_branch = ?1, git_head = ?2, git_dirty_files = ?3
+         WHERE id = (SELECT MAX(id) FROM turns WHERE session_id = ?4)",
+        params![git.branch, git.head_sha, git.dirty_files, session_id],
+    )?;
+    Ok(())
+}
+
+/// Record a commit and its files. `session_id` comes from the commit's
+/// `Claude-Track-Session` trailer, if any. Re-recording a SHA replaces it.
+pub fn insert_commit(
+    conn: &Connection,
+    session_id: Option<&str>,
+    repo: &str,
+    commit: &GitCommit,
+) -> Result<(), Box<dyn std::error::Error>> {
+    conn.execute(
+        "INSERT OR REPLACE INTO commits (sha, session_id, repo, branch, committed_at, message,
+            agent_lines, human_added, human_removed, agent_percentage)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+        params![
+            commit.sha,
+            session_id,
+            repo,
+            commit.branch,
+            commit.committed_at,
+            commit.message,
+            commit.attribution.as_ref().map(|a| a.agent_lines),
+            commit.attribution.as_ref().map(|a| a.human_added),
+            commit.attribution.as_ref().map(|a| a.human_removed),
+            commit.attribution.as_ref().map(|a| a.agent_percentage),
+        ],
+    )?;
+    conn.execute("DELETE FROM commit_files WHERE commit_sha = ?1", params![commit.sha])?;
+    for file in &commit.files {
+        conn.execute(
+            "INSERT INTO commit_files (commit_sha, path, additions, deletions) VALUES (?1, ?2, ?3, ?4)",
+            params![commit.sha, file.path, file.additions, file.deletions],
+        )?;
+    }
+    Ok(())
+}
+
+/// Inputs of successful Write, Edit and MultiEdit calls made since `since`,
+/// as `(tool_name, input_json)`.
+pub fn file_edits_since(conn: &Connection, since: &str) -> Result<Vec<(String, String)>, Box<dyn std::error::Error>> {
+    let mut stmt = conn.prepare(
+        "SELECT tool_name, input FROM tool_uses
+         WHERE tool_name IN ('Write', 'Edit', 'MultiEdit') AND timestamp >= ?1
+           AND COALESCE(status, 'ok') = 'ok' AND input IS NOT NULL
+         ORDER BY id",
+    )?;
+    let rows = stmt
+        .query_map(params![since], |row| Ok((row.get(0)?, row.get(1)?)))?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(rows)
+}
+
+/// Record the files a tool call read or changed, replacing any rows already
+/// stored for it.
+pub fn insert_file_changes(
+    conn: &Connection,
+    tool_use_id: &str,
+    session_id: &str,
+    timestamp: &str,
+    cwd: &str,
+    changes: &[FileChange],
+) -> Result<(), Box<dyn std::error::Error>> {
+    conn.execute("DELETE FROM file_changes WHERE tool_use_id = ?1", params![tool_use_id])?;
+    let mut stmt = conn.prepare(
+        "INSERT INTO file_changes (tool_use_id, session_id, timestamp, cwd, path, operation, lines_added, lines_removed)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+    )?;
+    for c in changes {
+        stmt.execute(params![
+            tool_use_id,
+            session_id,
+            timestamp,
+            cwd,
+            c.path,
+            c.operation,
+            c.lines_added,
+            c.lines_removed
+        ])?;
+    }
+    Ok(())
+}
+
+/// Record the commands parsed from a Bash tool call, replacing any rows
+/// already stored for it.
+pub fn insert_bash_commands(
+    conn: &Connection,
+    tool_use_id: &str,
+    session_id: &str,
+    timestamp: &str,
+    cwd: &str,
+    commands: &[BashCommand],
+) -> Result<(), Box<dyn std::error::Error>> {
+    conn.execute("DELETE FROM bash_commands WHERE tool_use_id = ?1", params![tool_use_id])?;
+    let mut stmt = conn.prepare(
+        "INSERT INTO bash_commands (tool_use_id, session_id, timestamp, cwd, position, program, subcommand, command, category)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+    )?;
+    for c in commands {
+        stmt.execute(params![
+            tool_use_id,
+            session_id,
+            timestamp,
+            cwd,
+            c.position,
+            c.program,
+            c.subcommand,
+            c.command,
+            c.category
+        ])?;
+    }
+    Ok(())
+}
+
+/// Whether commands are recorded for a Bash tool call.
+pub fn has_bash_commands(conn: &Connection, tool_use_id: &str) -> Result<bool, Box<dyn std::error::Error>> {
+    let found: bool = conn.query_row(
+        "SELECT EXISTS (SELECT 1 FROM bash_commands WHERE tool_use_id = ?1)",
+        params![tool_use_id],
+        |row| row.get(0),
+    )?;
+    Ok(found)
+}
+
+/// Bash tool calls with no parsed commands yet, as
+/// `(tool_use_id, session_id, timestamp, cwd, command)`.
+#[allow(clippy::type_complexity)]
+pub fn get_unparsed_bash_calls(
+    conn: &Connection,
+) -> Result<Vec<(String, String, String, String, String)>, Box<dyn std::error::Error>> {
+    let mut stmt = conn.prepare(
+        "SELECT tool_use_id, COALESCE(session_id, ''), COALESCE(timestamp, ''), COALESCE(cwd, ''),
+                json_extract(input, '$.command') AS cmd
+         FROM tool_uses t
+         WHERE tool_name = 'Bash' AND tool_use_id IS NOT NULL AND cmd IS NOT NULL
+           AND NOT EXISTS (SELECT 1 FROM bash_commands b WHERE b.tool_use_id = t.tool_use_id)
+         ORDER BY id",
+    )?;
+    let rows = stmt
+        .query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?, row.get(4)?)))?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(rows)
+}
+
+/// Record a WebFetch (`url`) or WebSearch (`query`) call. A call that's
+/// already recorded is left as is.
+#[allow(clippy::too_many_arguments)]
+pub fn insert_web_request(
+    conn: &Connection,
+    tool_use_id: &str,
+    session_id: &str,
+    timestamp: &str,
+    cwd: &str,
+    tool_name: &str,
+    domain: Option<&str>,
+    url: Option<&str>,
+    query: Option<&str>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    conn.execute(
+        "INSERT OR IGNORE INTO web_requests (tool_use_id, session_id, timestamp, cwd, tool_name, domain, url, query)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+        params![tool_use_id, session_id, timestamp, cwd, tool_name, domain, url, query],
+    )?;
+    Ok(())
+}
+
+/// Record how a web request ended: the response size, the HTTP status code
+/// when the tool reports one, and the tool call's outcome status.
+pub fn set_web_request_response(
+    conn: &Connection,
+    tool_use_id: &str,
+    response_bytes: i64,
+    status_code: Option<i64>,
+    status: &str,
+) -> Result<(), Box<dyn std::error::Error>> {
+    conn.execute(
+        "UPDATE web_requests SET response_bytes = ?1, status_code = ?2, status = ?3 WHERE tool_use_id = ?4",
+        params![response_bytes, status_code, status, tool_use_id],
+    )?;
+    Ok(())
+}
+
+/// Set the outcome status of a web request that got no PostToolUse, from its
+/// transcript result.
+pub fn set_web_request_status(conn: &Connection, tool_use_id: &str, status: &str) -> Result<(), Box<dyn std::error::Error>> {
+    conn.execute(
+        "UPDATE web_requests SET status = ?1 WHERE tool_use_id = ?2 AND status IS NULL",
+        params![status, tool_use_id],
+    )?;
+    Ok(())
+}
+
+/// Sessions whose working directory is `worktree` or a directory below it,
+/// oldest first.
+pub fn get_sessions_in_worktree(conn: &Connection, worktree: &str) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+    let mut stmt = conn.prepare(
+        "SELECT session_id FROM sessions
+         WHERE cwd = ?1 OR substr(cwd, 1, length(?1) + 1) = ?1 || '/'
+         ORDER BY started_at, session_id",
+    )?;
+    let rows = stmt.query_map(params![worktree], |row| row.get(0))?.collect::<Result<Vec<_>, _>>()?;
+    Ok(rows)
+}
+
+/// The most recently active session that is still open in `worktree` (or a
+/// directory below it) and has had activity since `since`.
+pub fn active_session(
+    conn: &Connection,
+    worktree: &str,
+    since: &str,
+) -> Result<Option<String>, Box<dyn std::error::Error>> {
+    let session = conn
+        .query_row(
+            "SELECT session_id FROM (
+                SELECT session_id,
+                       COALESCE((SELECT MAX(timestamp) FROM prompts p WHERE p.session_id = s.session_id), started_at) AS active_at
+                FROM sessions s
+                WHERE ended_at IS NULL AND (cwd = ?1 OR substr(cwd, 1, length(?1) + 1) = ?1 || '/')
+             )
+             WHERE active_at >= ?2
+             ORDER BY active_at DESC LIMIT 1",
+            params![worktree, since],
+            |row| row.get(0),
+        )
+        .ok();
+    Ok(session)
+}
+
+/// Split an MCP tool name, `mcp__<server>__<tool>`, at the first `__` after
+/// the prefix. None for built-in tools.
+fn mcp_parts(tool_name: &str) -> Option<(&str, &str)> {
+    tool_name
+        .strip_prefix("mcp__")?
+        .split_once("__")
+        .filter(|(server, _)| !server.is_empty())
+}
+
+/// Set `mcp_server` and `mcp_tool` on MCP calls recorded before the columns existed.
+fn backfill_mcp_columns(conn: &Connection) -> Result<(), Box<dyn std::error::Error>> {
+    let mut stmt = conn.prepare("SELECT id, tool_name FROM tool_uses WHERE substr(tool_name, 1, 5) = 'mcp__'")?;
+    let rows: Vec<(i64, String)> = stmt.query_map([], |r| Ok((r.get(0)?, r.get(1)?)))?.collect::<Result<_, _>>()?;
+    for (id, tool_name) in rows {
+        if let Some((server, tool)) = mcp_parts(&tool_name) {
+            conn.execute(
+                "UPDATE tool_uses SET mcp_server = ?1, mcp_tool = ?2 WHERE id = ?3",
+                params![server, tool, id],
+            )?;
+        }
+    }
+    Ok(())
+}
+
+fn mcp_server(tool_name: &str) -> Option<&str> {
+    mcp_parts(tool_name).map(|(server, _)| server)
+}
+
+fn mcp_tool(tool_name: &str) -> Option<&str> {
+    mcp_parts(tool_name).map(|(_, tool)| tool)
+}
+
+/// Insert a tool use record (from PreToolUse). `started_at` is a
+/// millisecond-precision timestamp used to compute the call's duration.
+/// The call joins the session's latest turn, which may already be closed
+/// when a Stop hook made Claude continue.
+#[allow(clippy::too_many_arguments)]
 pub fn insert_tool_use(
     conn: &Connection,
     tool_use_id: &str,
     session_id: &str,
     tool_name: &str,
     timestamp: &str,
+    started_at: &str,
     cwd: &str,
     input: &str,
 ) -> Result<(), Box<dyn std::error::Error>> {
     conn.execute(
-        "INSERT INTO tool_uses (tool_use_id, session_id, tool_name, timestamp, cwd, input)
-         VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
-        params![tool_use_id, session_id, tool_name, timestamp, cwd, input],
+        "INSERT INTO tool_uses (tool_use_id, session_id, tool_name, timestamp, started_at, cwd, input, mcp_server, mcp_tool, turn_id)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, (SELECT MAX(id) FROM turns WHERE session_id = ?2))",
+        params![tool_use_id, session_id, tool_name, timestamp, started_at, cwd, input, mcp_server(tool_name), mcp_tool(tool_name)],
     )?;
     Ok(())
 }
 
-/// Update an existing tool use with response_summary (from PostToolUse).
-/// If no matching row exists, inserts a new one.
+/// Update an existing tool use with response_summary and completion time
+/// (from PostToolUse), deriving duration_ms from the PreToolUse start time.
+/// If no matching row exists, inserts a new one with no duration.
+#[allow(clippy::too_many_arguments)]
 pub fn update_tool_use_response(
     conn: &Connection,
     tool_use_id: &str,
     session_id: &str,
     tool_name: &str,
     timestamp: &str,
+    completed_at: &str,
     cwd: &str,
     input: &str,
     response_summary: &str,
 ) -> Result<(), Box<dyn std::error::Error>> {
     let rows = conn.execute(
-        "UPDATE tool_uses SET response_summary = ?1 WHERE tool_use_id = ?2",
-        params![response_summary, tool_use_id],
+        "UPDATE tool_uses SET response_summary = ?1, completed_at = ?2,
+            duration_ms = CAST(ROUND((julianday(?2) - julianday(started_at)) * 86400000) AS INTEGER)
+         WHERE tool_use_id = ?3",
+        params![response_summary, completed_at, tool_use_id],
     )?;
     if rows == 0 {
         conn.execute(
-            "INSERT INTO tool_uses (tool_use_id, session_id, tool_name, timestamp, cwd, input, response_summary)
-             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
-            params![tool_use_id, session_id, tool_name, timestamp, cwd, input, response_summary],
+            "INSERT INTO tool_uses (tool_use_id, session_id, tool_name, timestamp, completed_at, cwd, input, response_summary, mcp_server, mcp_tool, turn_id)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, (SELECT MAX(id) FROM turns WHERE session_id = ?2))",
+            params![
+                tool_use_id,
+                session_id,
+                tool_name,
+                timestamp,
+                completed_at,
+                cwd,
+                input,
+                response_summary,
+                mcp_server(tool_name),
+                mcp_tool(tool_name)
+            ],
+        )?;
+    }
+    Ok(())
+}
+
+/// Record how a tool call ended. Called after `update_tool_use_response`, or
+/// at Stop for calls that never reached PostToolUse, so the row always exists.
+pub fn update_tool_use_outcome(
+    conn: &Connection,
+    tool_use_id: &str,
+    status: &str,
+    exit_code: Option<i64>,
+    error_text: Option<&str>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    conn.execute(
+        "UPDATE tool_uses SET status = ?1, exit_code = ?2, error_text = ?3 WHERE tool_use_id = ?4",
+        params![status, exit_code, error_text, tool_use_id],
+    )?;
+    Ok(())
+}
+
+/// Store the full (possibly capped) text of a tool response, replacing any
+/// previous copy. `original_bytes` is the size before capping.
+pub fn insert_tool_response(
+    conn: &Connection,
+    tool_use_id: &str,
+    body: &str,
+    original_bytes: usize,
+    truncated: bool,
+    compress: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    if compress {
+        conn.execute(
+            "INSERT OR REPLACE INTO tool_responses (tool_use_id, encoding, original_bytes, truncated, body)
+             VALUES (?1, 'zstd', ?2, ?3, ?4)",
+            params![tool_use_id, original_bytes as i64, truncated, compress_text(body)?],
+        )?;
+    } else {
+        conn.execute(
+            "INSERT OR REPLACE INTO tool_responses (tool_use_id, encoding, original_bytes, truncated, body)
+             VALUES (?1, 'identity', ?2, ?3, ?4)",
+            params![tool_use_id, original_bytes as i64, truncated, body],
         )?;
     }
     Ok(())
 }
 
-/// Insert a prompt record.
+/// Get the stored full response for a tool use, decompressing if needed.
+/// Returns `(text, truncated)`, or None if no full response was kept.
+pub fn get_tool_response(
+    conn: &Connection,
+    tool_use_id: &str,
+) -> Result<Option<(String, bool)>, Box<dyn std::error::Error>> {
+    let mut stmt = conn.prepare("SELECT body, truncated FROM tool_responses WHERE tool_use_id = ?1")?;
+    let mut rows = stmt.query(params![tool_use_id])?;
+    match rows.next()? {
+        Some(row) => {
+            let body: rusqlite::types::Value = row.get(0)?;
+            let truncated: bool = row.get(1)?;
+            let text = match body {
+                rusqlite::types::Value::Text(s) => s,
+                rusqlite::types::Value::Blob(b) => decompress_text(&b)?,
+                _ => String::new(),
+            };
+            Ok(Some((text, truncated)))
+        }
+        None => Ok(None),
+    }
+}
+
+/// Compress text into a zstd frame.
+pub fn compress_text(text: &str) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+    Ok(zstd::encode_all(text.as_bytes(), 3)?)
+}
+
+/// Decompress a zstd frame produced by `compress_text`.
+pub fn decompress_text(bytes: &[u8]) -> Result<String, Box<dyn std::error::Error>> {
+    Ok(String::from_utf8(zstd::decode_all(bytes)?)?)
+}
+
+/// True if `bytes` start with the zstd frame magic number.
+pub fn is_zstd(bytes: &[u8]) -> bool {
+    bytes.starts_with(&[0x28, 0xb5, 0x2f, 0xfd])
+}
+
+/// Insert a prompt record. Returns its id.
 pub fn insert_prompt(
     conn: &Connection,
     session_id: &str,
     timestamp: &str,
     prompt_text: &str,
-) -> Result<(), Box<dyn std::error::Error>> {
+) -> Result<i64, Box<dyn std::error::Error>> {
     conn.execute(
         "INSERT INTO prompts (session_id, timestamp, prompt_text) VALUES (?1, ?2, ?3)",
         params![session_id, timestamp, prompt_text],
     )?;
+    Ok(conn.last_insert_rowid())
+}
+
+/// Record how a prompt was captured, when not in full. `length` is the
+/// original length in chars, if known.
+pub fn set_prompt_capture(
+    conn: &Connection,
+    prompt_id: i64,
+    capture_level: &str,
+    length: Option<i64>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    conn.execute(
+        "UPDATE prompts SET capture_level = ?1, prompt_length = ?2 WHERE id = ?3",
+        params![capture_level, length, prompt_id],
+    )?;
+    Ok(())
+}
+
+/// The salt for hashed capture, generated on first use. It stays in this
+/// database so equal texts hash equally across sessions.
+pub fn capture_salt(conn: &Connection) -> Result<String, Box<dyn std::error::Error>> {
+    use std::hash::{BuildHasher, Hasher};
+    let existing = conn
+        .query_row("SELECT value FROM settings WHERE key = 'capture_salt'", [], |r| r.get::<_, String>(0))
+        .ok();
+    if let Some(salt) = existing {
+        return Ok(salt);
+    }
+    // RandomState is randomly keyed per process
+    let salt: String = (0..2)
+        .map(|_| format!("{:016x}", std::collections::hash_map::RandomState::new().build_hasher().finish()))
+        .collect();
+    conn.execute("INSERT OR IGNORE INTO settings (key, value) VALUES ('capture_salt', ?1)", params![salt])?;
+    Ok(conn.query_row("SELECT value FROM settings WHERE key = 'capture_salt'", [], |r| r.get(0))?)
+}
+
+/// Open a turn for a prompt, closing any turn the session left open (a
+/// prompt submitted without a Stop in between, e.g. after an interrupt).
+/// `start_offset` is the transcript length when the prompt was submitted.
+/// Returns the new turn's id.
+pub fn open_turn(
+    conn: &Connection,
+    session_id: &str,
+    prompt_id: i64,
+    started_at: &str,
+    start_offset: i64,
+) -> Result<i64, Box<dyn std::error::Error>> {
+    conn.execute(
+        "UPDATE turns SET ended_at = ?1 WHERE session_id = ?2 AND ended_at IS NULL",
+        params![started_at, session_id],
+    )?;
+    conn.execute(
+        "INSERT INTO turns (session_id, prompt_id, started_at, start_offset) VALUES (?1, ?2, ?3, ?4)",
+        params![session_id, prompt_id, started_at, start_offset],
+    )?;
+    Ok(conn.last_insert_rowid())
+}
+
+/// Close the session's most recent turn at Stop. A turn already closed is
+/// extended, since Claude can continue after a Stop hook blocks. With no
+/// transcript to measure, `end_offset` is None and the offset is left as is.
+pub fn close_turn(
+    conn: &Connection,
+    session_id: &str,
+    ended_at: &str,
+    end_offset: Option<i64>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    conn.execute(
+        "UPDATE turns SET ended_at = ?1, end_offset = COALESCE(?2, end_offset)
+         WHERE id = (SELECT MAX(id) FROM turns WHERE session_id = ?3)",
+        params![ended_at, end_offset, session_id],
+    )?;
+    Ok(())
+}
+
+/// Store the final assistant message on the session's latest turn. A later
+/// Stop in the same turn replaces it; `stop_hook_active` stays set once seen.
+pub fn set_turn_answer(
+    conn: &Connection,
+    session_id: &str,
+    answer: &str,
+    length: Option<i64>,
+    truncated: bool,
+    stop_hook_active: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    conn.execute(
+        "UPDATE turns SET answer = ?1, answer_length = ?2, answer_truncated = ?3,
+            stop_hook_active = MAX(COALESCE(stop_hook_active, 0), ?4)
+         WHERE id = (SELECT MAX(id) FROM turns WHERE session_id = ?5)",
+        params![answer, length, truncated, stop_hook_active, session_id],
+    )?;
+    Ok(())
+}
+
+/// Place the session's unassigned API calls in turns by transcript offset
+/// (the latest turn starting at or before the call), then recompute every
+/// turn's token totals from its calls.
+pub fn assign_api_calls_to_turns(conn: &Connection, session_id: &str) -> Result<(), Box<dyn std::error::Error>> {
+    conn.execute(
+        "UPDATE api_calls SET turn_id = (
+            SELECT t.id FROM turns t
+            WHERE t.session_id = api_calls.session_id AND t.start_offset <= api_calls.transcript_offset
+            ORDER BY t.start_offset DESC, t.id DESC LIMIT 1)
+         WHERE session_id = ?1 AND turn_id IS NULL",
+        params![session_id],
+    )?;
+    conn.execute(
+        "UPDATE turns SET
+            input_tokens = (SELECT COALESCE(SUM(input_tokens), 0) FROM api_calls a WHERE a.turn_id = turns.id),
+            cache_creation_tokens = (SELECT COALESCE(SUM(cache_creation_tokens), 0) FROM api_calls a WHERE a.turn_id = turns.id),
+            cache_read_tokens = (SELECT COALESCE(SUM(cache_read_tokens), 0) FROM api_calls a WHERE a.turn_id = turns.id),
+            output_tokens = (SELECT COALESCE(SUM(output_tokens), 0) FROM api_calls a WHERE a.turn_id = turns.id),
+            api_call_count = (SELECT COUNT(*) FROM api_calls a WHERE a.turn_id = turns.id)
+         WHERE session_id = ?1",
+        params![session_id],
+    )?;
     Ok(())
 }
 
 /// Get current token state and offset for a session. Returns None if no row exists.
 /// Returns: (input_tokens, cache_creation, cache_read, output_tokens, api_call_count, last_transcript_offset, model)
+#[allow(clippy::type_complexity)]
 pub fn get_session_token_state(
     conn: &Connection,
     session_id: &str,
@@ -201,9 +1084,264 @@ pub fn get_session_token_state(
     Ok(result)
 }
 
+/// Insert an API call, or merge it into the existing row for the same message
+/// (keeping the larger token counts), so re-parsing a transcript is idempotent.
+pub fn upsert_api_call(
+    conn: &Connection,
+    session_id: &str,
+    call: &ApiCall,
+) -> Result<(), Box<dyn std::error::Error>> {
+    conn.execute(
+        "INSERT INTO api_calls (session_id, message_id, request_id, timestamp, model, stop_reason,
+            input_tokens, cache_creation_tokens, cache_read_tokens, output_tokens, transcript_offset,
+            context_tokens)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?7 + ?8 + ?9)
+         ON CONFLICT (session_id, message_id) DO UPDATE SET
+            transcript_offset = COALESCE(transcript_offset, excluded.transcript_offset),
+            request_id = COALESCE(request_id, excluded.request_id),
+            timestamp = COALESCE(timestamp, excluded.timestamp),
+            model = COALESCE(NULLIF(model, ''), excluded.model),
+            stop_reason = COALESCE(excluded.stop_reason, stop_reason),
+            input_tokens = MAX(input_tokens, excluded.input_tokens),
+            cache_creation_tokens = MAX(cache_creation_tokens, excluded.cache_creation_tokens),
+            cache_read_tokens = MAX(cache_read_tokens, excluded.cache_read_tokens),
+            output_tokens = MAX(output_tokens, excluded.output_tokens),
+            context_tokens = MAX(input_tokens, excluded.input_tokens)
+                + MAX(cache_creation_tokens, excluded.cache_creation_tokens)
+                + MAX(cache_read_tokens, excluded.cache_read_tokens)",
+        params![
+            session_id,
+            call.message_id,
+            call.request_id,
+            call.timestamp,
+            call.model,
+            call.stop_reason,
+            call.input_tokens,
+            call.cache_creation_tokens,
+            call.cache_read_tokens,
+            call.output_tokens,
+            call.transcript_offset as i64,
+        ],
+    )?;
+    Ok(())
+}
+
+/// Insert a subagent for a Task/Agent tool call. Ignored if it already exists.
+#[allow(clippy::too_many_arguments)]
+pub fn insert_subagent(
+    conn: &Connection,
+    tool_use_id: &str,
+    session_id: &str,
+    subagent_type: &str,
+    description: &str,
+    prompt: &str,
+    requested_model: Option<&str>,
+    started_at: Option<&str>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    conn.execute(
+        "INSERT OR IGNORE INTO subagents
+            (tool_use_id, session_id, subagent_type, description, prompt, requested_model, started_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+        params![tool_use_id, session_id, subagent_type, description, prompt, requested_model, started_at],
+    )?;
+    Ok(())
+}
+
+/// Mark a subagent finished, deriving duration_ms from its start time.
+pub fn complete_subagent(
+    conn: &Connection,
+    tool_use_id: &str,
+    completed_at: &str,
+) -> Result<(), Box<dyn std::error::Error>> {
+    conn.execute(
+        "UPDATE subagents SET completed_at = ?1,
+            duration_ms = CAST(ROUND((julianday(?1) - julianday(started_at)) * 86400000) AS INTEGER)
+         WHERE tool_use_id = ?2",
+        params![completed_at, tool_use_id],
+    )?;
+    Ok(())
+}
+
+/// Find the subagent whose sidechain starts at `root_uuid`, claiming the
+/// earliest unclaimed subagent in the session with the same prompt if none has yet.
+pub fn claim_subagent_root(
+    conn: &Connection,
+    session_id: &str,
+    prompt: &str,
+    root_uuid: &str,
+) -> Result<Option<String>, Box<dyn std::error::Error>> {
+    let claimed: Option<String> = conn
+        .query_row(
+            "SELECT tool_use_id FROM subagents WHERE root_uuid = ?1",
+            params![root_uuid],
+            |row| row.get(0),
+        )
+        .ok();
+    if claimed.is_some() {
+        return Ok(claimed);
+    }
+    let candidate: Option<String> = conn
+        .query_row(
+            "SELECT tool_use_id FROM subagents
+             WHERE session_id = ?1 AND prompt = ?2 AND root_uuid IS NULL
+             ORDER BY started_at, rowid LIMIT 1",
+            params![session_id, prompt],
+            |row| row.get(0),
+        )
+        .ok();
+    if let Some(id) = &candidate {
+        conn.execute(
+            "UPDATE subagents SET root_uuid = ?1 WHERE tool_use_id = ?2",
+            params![root_uuid, id],
+        )?;
+    }
+    Ok(candidate)
+}
+
+/// Record which subagent a sidechain transcript line belongs to.
+pub fn insert_sidechain_line(
+    conn: &Connection,
+    uuid: &str,
+    session_id: &str,
+    subagent_id: &str,
+) -> Result<(), Box<dyn std::error::Error>> {
+    conn.execute(
+        "INSERT OR REPLACE INTO sidechain_lines (uuid, session_id, subagent_id) VALUES (?1, ?2, ?3)",
+        params![uuid, session_id, subagent_id],
+    )?;
+    Ok(())
+}
+
+/// The subagent a previously seen sidechain line belongs to, if any.
+pub fn get_sidechain_owner(conn: &Connection, uuid: &str) -> Result<Option<String>, Box<dyn std::error::Error>> {
+    Ok(conn
+        .query_row(
+            "SELECT subagent_id FROM sidechain_lines WHERE uuid = ?1",
+            params![uuid],
+            |row| row.get(0),
+        )
+        .ok())
+}
+
+/// Attribute an API call to a subagent.
+pub fn set_api_call_subagent(
+    conn: &Connection,
+    session_id: &str,
+    message_id: &str,
+    subagent_id: &str,
+) -> Result<(), Box<dyn std::error::Error>> {
+    conn.execute(
+        "UPDATE api_calls SET subagent_id = ?1 WHERE session_id = ?2 AND message_id = ?3",
+        params![subagent_id, session_id, message_id],
+    )?;
+    Ok(())
+}
+
+/// Attribute a tool use to a subagent.
+pub fn set_tool_use_subagent(
+    conn: &Connection,
+    tool_use_id: &str,
+    subagent_id: &str,
+) -> Result<(), Box<dyn std::error::Error>> {
+    conn.execute(
+        "UPDATE tool_uses SET subagent_id = ?1 WHERE tool_use_id = ?2",
+        params![subagent_id, tool_use_id],
+    )?;
+    Ok(())
+}
+
+/// Set the context window and high-use flag on a session's API calls, per
+/// model (see `context::limit`), and on each of its compactions the context
+/// size of the last main-conversation call before it.
+pub fn update_context_usage(conn: &Connection, session_id: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let mut stmt = conn.prepare(
+        "SELECT COALESCE(model, ''), MAX(context_tokens) FROM api_calls WHERE session_id = ?1 GROUP BY 1",
+    )?;
+    let peaks: Vec<(String, Option<i64>)> = stmt
+        .query_map(params![session_id], |r| Ok((r.get(0)?, r.get(1)?)))?
+        .collect::<Result<_, _>>()?;
+    for (model, peak) in peaks {
+        let limit = context::limit(&model, peak.unwrap_or(0));
+        conn.execute(
+            "UPDATE api_calls SET context_limit = ?1,
+                context_high = context_tokens * 100 > ?1 * ?2
+             WHERE session_id = ?3 AND COALESCE(model, '') = ?4",
+            params![limit, context::HIGH_USE_PERCENT, session_id, model],
+        )?;
+    }
+    conn.execute(
+        "UPDATE compactions SET context_tokens = (
+            SELECT a.context_tokens FROM api_calls a
+            WHERE a.session_id = compactions.session_id AND a.subagent_id IS NULL
+              AND julianday(a.timestamp) <= julianday(compactions.timestamp)
+            ORDER BY julianday(a.timestamp) DESC LIMIT 1
+         )
+         WHERE session_id = ?1",
+        params![session_id],
+    )?;
+    Ok(())
+}
+
+/// Sessions with API calls whose context window hasn't been set.
+pub fn get_sessions_without_context_limits(conn: &Connection) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+    let mut stmt =
+        conn.prepare("SELECT DISTINCT session_id FROM api_calls WHERE context_limit IS NULL ORDER BY session_id")?;
+    let ids = stmt.query_map([], |r| r.get(0))?.collect::<Result<_, _>>()?;
+    Ok(ids)
+}
+
+/// Whether any API calls are recorded for a session.
+pub fn has_api_calls(conn: &Connection, session_id: &str) -> Result<bool, Box<dyn std::error::Error>> {
+    let found: bool = conn.query_row(
+        "SELECT EXISTS (SELECT 1 FROM api_calls WHERE session_id = ?1)",
+        params![session_id],
+        |row| row.get(0),
+    )?;
+    Ok(found)
+}
+
+/// Remove all API calls for a session (used when its transcript is rewritten).
+pub fn delete_api_calls(conn: &Connection, session_id: &str) -> Result<(), Box<dyn std::error::Error>> {
+    conn.execute("DELETE FROM api_calls WHERE session_id = ?1", params![session_id])?;
+    Ok(())
+}
+
+/// Sum a session's API calls. The model is that of the earliest call.
+pub fn get_session_api_totals(
+    conn: &Connection,
+    session_id: &str,
+) -> Result<AggregatedTokenUsage, Box<dyn std::error::Error>> {
+    let mut totals = conn.query_row(
+        "SELECT COALESCE(SUM(input_tokens), 0), COALESCE(SUM(cache_creation_tokens), 0),
+                COALESCE(SUM(cache_read_tokens), 0), COALESCE(SUM(output_tokens), 0), COUNT(*)
+         FROM api_calls WHERE session_id = ?1",
+        params![session_id],
+        |row| {
+            Ok(AggregatedTokenUsage {
+                model: String::new(),
+                input_tokens: row.get(0)?,
+                cache_creation_tokens: row.get(1)?,
+                cache_read_tokens: row.get(2)?,
+                output_tokens: row.get(3)?,
+                api_call_count: row.get(4)?,
+            })
+        },
+    )?;
+    totals.model = conn
+        .query_row(
+            "SELECT model FROM api_calls WHERE session_id = ?1 AND model != ''
+             ORDER BY timestamp, rowid LIMIT 1",
+            params![session_id],
+            |row| row.get(0),
+        )
+        .unwrap_or_default();
+    Ok(totals)
+}
+
 /// Upsert a token usage record. If a row already exists for this session_id,
 /// update it with the new cumulative totals. Otherwise insert a new row.
 /// This ensures only one token_usage row per session.
+#[allow(clippy::too_many_arguments)]
 pub fn insert_token_usage(
     conn: &Connection,
     session_id: &str,
@@ -263,9 +1401,9 @@ pub fn insert_migrated_tool_use(
     input: &str,
 ) -> Result<(), Box<dyn std::error::Error>> {
     conn.execute(
-        "INSERT INTO tool_uses (session_id, tool_name, timestamp, cwd, input)
-         VALUES (?1, ?2, ?3, ?4, ?5)",
-        params![session_id, tool_name, timestamp, cwd, input],
+        "INSERT INTO tool_uses (session_id, tool_name, timestamp, cwd, input, mcp_server, mcp_tool)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+        params![session_id, tool_name, timestamp, cwd, input, mcp_server(tool_name), mcp_tool(tool_name)],
     )?;
     Ok(())
 }
@@ -301,13 +1439,100 @@ pub fn insert_plan(
     plan_text: &str,
 ) -> Result<(), Box<dyn std::error::Error>> {
     conn.execute(
-        "INSERT INTO plans (session_id, tool_use_id, timestamp, plan_text)
-         VALUES (?1, ?2, ?3, ?4)",
+        "INSERT INTO plans (session_id, tool_use_id, timestamp, plan_text, turn_id)
+         VALUES (?1, ?2, ?3, ?4, (SELECT MAX(id) FROM turns WHERE session_id = ?1))",
         params![session_id, tool_use_id, timestamp, plan_text],
     )?;
     Ok(())
 }
 
+/// Record whether a plan was accepted, and the user's feedback if it was
+/// rejected.
+pub fn set_plan_outcome(
+    conn: &Connection,
+    tool_use_id: &str,
+    accepted: bool,
+    feedback: Option<&str>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    conn.execute(
+        "UPDATE plans SET accepted = ?1, feedback = ?2 WHERE tool_use_id = ?3",
+        params![accepted, feedback, tool_use_id],
+    )?;
+    Ok(())
+}
+
+/// Tool use ids of plans whose outcome isn't known yet, in one session or,
+/// with `None`, in all sessions.
+pub fn get_pending_plan_ids(
+    conn: &Connection,
+    session_id: Option<&str>,
+) -> Result<std::collections::HashSet<String>, Box<dyn std::error::Error>> {
+    let mut stmt = conn.prepare(
+        "SELECT tool_use_id FROM plans
+         WHERE accepted IS NULL AND tool_use_id IS NOT NULL AND (?1 IS NULL OR session_id = ?1)",
+    )?;
+    let ids = stmt.query_map(params![session_id], |row| row.get(0))?.collect::<Result<_, _>>()?;
+    Ok(ids)
+}
+
+/// Tool use ids of the session's calls with no outcome yet: no PostToolUse
+/// arrived for them.
+pub fn get_unresolved_tool_use_ids(
+    conn: &Connection,
+    session_id: &str,
+) -> Result<std::collections::HashSet<String>, Box<dyn std::error::Error>> {
+    let mut stmt = conn.prepare(
+        "SELECT tool_use_id FROM tool_uses WHERE status IS NULL AND tool_use_id IS NOT NULL AND session_id = ?1",
+    )?;
+    let ids = stmt.query_map(params![session_id], |row| row.get(0))?.collect::<Result<_, _>>()?;
+    Ok(ids)
+}
+
+/// Insert a notification record (from Notification).
+pub fn insert_notification(
+    conn: &Connection,
+    session_id: &str,
+    timestamp: &str,
+    notification_type: &str,
+    message: &str,
+) -> Result<(), Box<dyn std::error::Error>> {
+    conn.execute(
+        "INSERT INTO notifications (session_id, timestamp, notification_type, message)
+         VALUES (?1, ?2, ?3, ?4)",
+        params![session_id, timestamp, notification_type, message],
+    )?;
+    Ok(())
+}
+
+/// Insert a subagent stop record (from SubagentStop).
+pub fn insert_subagent_stop(
+    conn: &Connection,
+    session_id: &str,
+    timestamp: &str,
+) -> Result<(), Box<dyn std::error::Error>> {
+    conn.execute(
+        "INSERT INTO subagent_stops (session_id, timestamp) VALUES (?1, ?2)",
+        params![session_id, timestamp],
+    )?;
+    Ok(())
+}
+
+/// Insert a compaction record (from PreCompact).
+pub fn insert_compaction(
+    conn: &Connection,
+    session_id: &str,
+    timestamp: &str,
+    trigger: &str,
+    custom_instructions: &str,
+) -> Result<(), Box<dyn std::error::Error>> {
+    conn.execute(
+        "INSERT INTO compactions (session_id, timestamp, trigger, custom_instructions)
+         VALUES (?1, ?2, ?3, ?4)",
+        params![session_id, timestamp, trigger, custom_instructions],
+    )?;
+    Ok(())
+}
+
 /// Get all plan tool_use_ids for batch deduplication.
 pub fn get_all_plan_tool_use_ids(
     conn: &Connection,
@@ -320,7 +1545,20 @@ pub fn get_all_plan_tool_use_ids(
     Ok(ids)
 }
 
-/// Get the transcript_path for a given session.
+/// Get the working directory recorded at SessionStart.
+pub fn get_session_cwd(conn: &Connection, session_id: &str) -> Result<Option<String>, Box<dyn std::error::Error>> {
+    let cwd = conn
+        .query_row("SELECT cwd FROM sessions WHERE session_id = ?1", params![session_id], |row| {
+            row.get::<_, Option<String>>(0)
+        })
+        .ok()
+        .flatten()
+        .filter(|c| !c.is_empty());
+    Ok(cwd)
+}
+
+/// Get the transcript_path recorded for a given session. The file may since
+/// have been pruned; see `archive::restore_transcript` for the archived copy.
 pub fn get_transcript_path(
     conn: &Connection,
     session_id: &str,
@@ -334,6 +1572,74 @@ pub fn get_transcript_path(
     Ok(result)
 }
 
+/// Total bytes of a session's transcript already archived.
+pub fn get_archived_transcript_length(conn: &Connection, session_id: &str) -> Result<i64, Box<dyn std::error::Error>> {
+    Ok(conn.query_row(
+        "SELECT COALESCE(SUM(length), 0) FROM transcript_chunks WHERE session_id = ?1",
+        params![session_id],
+        |row| row.get(0),
+    )?)
+}
+
+/// Record an archived chunk covering `length` bytes from `offset`.
+pub fn insert_transcript_chunk(
+    conn: &Connection,
+    session_id: &str,
+    offset: i64,
+    length: i64,
+    sha256: &str,
+) -> Result<(), Box<dyn std::error::Error>> {
+    conn.execute(
+        "INSERT OR REPLACE INTO transcript_chunks (session_id, offset, length, sha256) VALUES (?1, ?2, ?3, ?4)",
+        params![session_id, offset, length, sha256],
+    )?;
+    Ok(())
+}
+
+/// A session's archived chunks in order, as `(offset, length, sha256)`.
+#[allow(clippy::type_complexity)]
+pub fn get_transcript_chunks(
+    conn: &Connection,
+    session_id: &str,
+) -> Result<Vec<(i64, i64, String)>, Box<dyn std::error::Error>> {
+    let mut stmt =
+        conn.prepare("SELECT offset, length, sha256 FROM transcript_chunks WHERE session_id = ?1 ORDER BY offset")?;
+    let rows = stmt
+        .query_map(params![session_id], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(rows)
+}
+
+/// Point every chunk stored as object `old_sha` at `new_sha` instead.
+pub fn replace_transcript_chunk_object(
+    conn: &Connection,
+    old_sha: &str,
+    new_sha: &str,
+) -> Result<(), Box<dyn std::error::Error>> {
+    conn.execute("UPDATE transcript_chunks SET sha256 = ?1 WHERE sha256 = ?2", params![new_sha, old_sha])?;
+    Ok(())
+}
+
+/// Whether any archived chunk is stored as object `sha`.
+pub fn is_transcript_object_used(conn: &Connection, sha: &str) -> Result<bool, Box<dyn std::error::Error>> {
+    Ok(conn.query_row("SELECT EXISTS (SELECT 1 FROM transcript_chunks WHERE sha256 = ?1)", params![sha], |r| {
+        r.get(0)
+    })?)
+}
+
+/// Forget a session's archived chunks (the objects themselves may be shared).
+pub fn delete_transcript_chunks(conn: &Connection, session_id: &str) -> Result<(), Box<dyn std::error::Error>> {
+    conn.execute("DELETE FROM transcript_chunks WHERE session_id = ?1", params![session_id])?;
+    Ok(())
+}
+
+/// Sessions with an archived transcript.
+pub fn get_archived_session_ids(conn: &Connection) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+    let mut stmt = conn.prepare("SELECT DISTINCT session_id FROM transcript_chunks ORDER BY session_id")?;
+    let rows = stmt.query_map([], |row| row.get(0))?.collect::<Result<Vec<_>, _>>()?;
+    Ok(rows)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -360,6 +1666,10 @@ mod tests {
         assert!(tables.contains(&"prompts".to_string()));
         assert!(tables.contains(&"token_usage".to_string()));
         assert!(tables.contains(&"plans".to_string()));
+        assert!(tables.contains(&"events".to_string()));
+        assert!(tables.contains(&"notifications".to_string()));
+        assert!(tables.contains(&"subagent_stops".to_string()));
+        assert!(tables.contains(&"compactions".to_string()));
     }
 
     #[test]
@@ -369,6 +1679,23 @@ mod tests {
         init_db(&conn).unwrap();
     }
 
+    #[test]
+    fn init_db_recreates_views_on_version_change() {
+        let conn = mem_db();
+        let version: i64 = conn.query_row("PRAGMA user_version", [], |r| r.get(0)).unwrap();
+        assert_eq!(version, SCHEMA_VERSION);
+
+        // A view from an older schema version is replaced; the current one is kept
+        conn.execute_batch("DROP VIEW session_model_usage; CREATE VIEW session_model_usage AS SELECT 1 AS stale;")
+            .unwrap();
+        init_db(&conn).unwrap();
+        assert!(conn.prepare("SELECT stale FROM session_model_usage").is_ok());
+        conn.execute_batch("PRAGMA user_version = 0;").unwrap();
+        init_db(&conn).unwrap();
+        assert!(conn.prepare("SELECT stale FROM session_model_usage").is_err());
+        assert!(conn.prepare("SELECT api_call_count FROM session_model_usage").is_ok());
+    }
+
     #[test]
     fn session_start_and_end() {
         let conn = mem_db();
@@ -423,7 +1750,7 @@ mod tests {
     #[test]
     fn tool_use_insert_and_update() {
         let conn = mem_db();
-        insert_tool_use(&conn, "tu1", "s1", "Read", "ts1", "/proj", r#"{"file_path":"/foo"}"#).unwrap();
+        insert_tool_use(&conn, "tu1", "s1", "Read", "ts1", "ts1", "/proj", r#"{"file_path":"/foo"}"#).unwrap();
 
         let (tool, input): (String, String) = conn
             .query_row("SELECT tool_name, input FROM tool_uses WHERE tool_use_id='tu1'", [], |row| {
@@ -433,7 +1760,7 @@ mod tests {
         assert_eq!(tool, "Read");
         assert!(input.contains("file_path"));
 
-        update_tool_use_response(&conn, "tu1", "s1", "Read", "ts1", "/proj", "{}", "ok").unwrap();
+        update_tool_use_response(&conn, "tu1", "s1", "Read", "ts1", "ts1", "/proj", "{}", "ok").unwrap();
         let resp: String = conn
             .query_row("SELECT response_summary FROM tool_uses WHERE tool_use_id='tu1'", [], |row| row.get(0))
             .unwrap();
@@ -444,7 +1771,7 @@ mod tests {
     fn tool_use_update_without_pre() {
         let conn = mem_db();
         // PostToolUse without matching PreToolUse — should insert new row
-        update_tool_use_response(&conn, "tu2", "s1", "Bash", "ts2", "/proj", r#"{"cmd":"ls"}"#, "output").unwrap();
+        update_tool_use_response(&conn, "tu2", "s1", "Bash", "ts2", "ts2", "/proj", r#"{"cmd":"ls"}"#, "output").unwrap();
         let count: i64 = conn
             .query_row("SELECT COUNT(*) FROM tool_uses WHERE tool_use_id='tu2'", [], |row| row.get(0))
             .unwrap();
@@ -461,6 +1788,97 @@ mod tests {
         assert_eq!(text, "hello world");
     }
 
+    #[test]
+    fn open_turn_closes_previous_open_turn() {
+        let conn = mem_db();
+        let p1 = insert_prompt(&conn, "s1", "ts1", "one").unwrap();
+        let t1 = open_turn(&conn, "s1", p1, "ts1", 0).unwrap();
+        // No Stop in between, e.g. the turn was interrupted
+        let p2 = insert_prompt(&conn, "s1", "ts2", "two").unwrap();
+        let t2 = open_turn(&conn, "s1", p2, "ts2", 500).unwrap();
+        let ended: Option<String> = conn
+            .query_row("SELECT ended_at FROM turns WHERE id = ?1", params![t1], |r| r.get(0))
+            .unwrap();
+        assert_eq!(ended.as_deref(), Some("ts2"));
+
+        insert_tool_use(&conn, "tu1", "s1", "Read", "ts2", "ts2", "/p", "{}").unwrap();
+        let turn: Option<i64> = conn
+            .query_row("SELECT turn_id FROM tool_uses WHERE tool_use_id='tu1'", [], |r| r.get(0))
+            .unwrap();
+        assert_eq!(turn, Some(t2));
+    }
+
+    #[test]
+    fn api_calls_assigned_to_turns_by_offset() {
+        let conn = mem_db();
+        let call = |id: &str, offset: u64, output: i64| ApiCall {
+            message_id: id.to_string(),
+            output_tokens: output,
+            transcript_offset: offset,
+            ..Default::default()
+        };
+        let t1 = open_turn(&conn, "s1", 1, "ts1", 100).unwrap();
+        let t2 = open_turn(&conn, "s1", 2, "ts2", 300).unwrap();
+        upsert_api_call(&conn, "s1", &call("m0", 0, 1)).unwrap();
+        upsert_api_call(&conn, "s1", &call("m1", 150, 10)).unwrap();
+        upsert_api_call(&conn, "s1", &call("m2", 250, 20)).unwrap();
+        upsert_api_call(&conn, "s1", &call("m3", 300, 40)).unwrap();
+        assign_api_calls_to_turns(&conn, "s1").unwrap();
+
+        let totals = |id: i64| -> (i64, i64) {
+            conn.query_row("SELECT output_tokens, api_call_count FROM turns WHERE id = ?1", params![id], |r| {
+                Ok((r.get(0)?, r.get(1)?))
+            })
+            .unwrap()
+        };
+        assert_eq!(totals(t1), (30, 2));
+        assert_eq!(totals(t2), (40, 1));
+        let unassigned: i64 = conn
+            .query_row("SELECT COUNT(*) FROM api_calls WHERE turn_id IS NULL", [], |r| r.get(0))
+            .unwrap();
+        assert_eq!(unassigned, 1);
+    }
+
+    #[test]
+    fn active_session_matches_open_session_in_worktree() {
+        let conn = mem_db();
+        insert_session_start(&conn, "old", "2026-02-27T08:00:00Z", "startup", "/repo", "/t").unwrap();
+        insert_session_start(&conn, "sub", "2026-02-27T09:00:00Z", "startup", "/repo/src", "/t").unwrap();
+        insert_session_start(&conn, "ended", "2026-02-27T10:00:00Z", "startup", "/repo", "/t").unwrap();
+        update_session_end(&conn, "ended", "2026-02-27T10:30:00Z", "logout").unwrap();
+        insert_session_start(&conn, "other", "2026-02-27T11:00:00Z", "startup", "/repo2", "/t").unwrap();
+        // "old" started first but has the latest prompt
+        insert_prompt(&conn, "old", "2026-02-27T12:00:00Z", "hi").unwrap();
+
+        let since = "2026-02-27T00:00:00Z";
+        assert_eq!(active_session(&conn, "/repo", since).unwrap().as_deref(), Some("old"));
+        assert_eq!(active_session(&conn, "/repo", "2026-02-28T00:00:00Z").unwrap(), None);
+        assert_eq!(active_session(&conn, "/nowhere", since).unwrap(), None);
+    }
+
+    #[test]
+    fn insert_commit_replaces_files() {
+        let conn = mem_db();
+        let file = |path: &str, additions: i64| crate::models::CommitFile {
+            path: path.to_string(),
+            additions: Some(additions),
+            deletions: Some(0),
+        };
+        let mut commit = GitCommit { sha: "abc".into(), message: "msg".into(), files: vec![file("a", 1), file("b", 2)], ..Default::default() };
+        insert_commit(&conn, Some("s1"), "/repo", &commit).unwrap();
+        commit.files = vec![file("a", 3)];
+        insert_commit(&conn, None, "/repo", &commit).unwrap();
+
+        let (session, files): (Option<String>, i64) = conn
+            .query_row(
+                "SELECT session_id, (SELECT SUM(additions) FROM commit_files WHERE commit_sha = sha) FROM commits",
+                [],
+                |r| Ok((r.get(0)?, r.get(1)?)),
+            )
+            .unwrap();
+        assert_eq!((session, files), (None, 3));
+    }
+
     #[test]
     fn token_usage_insert() {
         let conn = mem_db();
@@ -527,6 +1945,21 @@ mod tests {
         assert_eq!(path.unwrap(), "/tmp/t.jsonl");
     }
 
+    #[test]
+    fn get_transcript_path_does_not_restore() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let conn = open_db(&dir.path().join("claude-track.db")).unwrap();
+        let original = dir.path().join("s1.jsonl");
+        std::fs::write(&original, "{}\n").unwrap();
+        insert_session_start(&conn, "s1", "ts", "startup", "/proj", original.to_str().unwrap()).unwrap();
+        let redactor = crate::redact::Redactor::new(&crate::config::RedactionConfig::default()).unwrap();
+        crate::archive::archive_transcript(&conn, "s1", &original, &redactor, crate::config::CaptureLevel::Full).unwrap();
+
+        std::fs::remove_file(&original).unwrap();
+        assert_eq!(get_transcript_path(&conn, "s1").unwrap().as_deref(), original.to_str());
+        assert!(!dir.path().join("claude-track-archive").join("transcripts").exists());
+    }
+
     #[test]
     fn get_transcript_path_not_found() {
         let conn = mem_db();
@@ -657,6 +2090,75 @@ mod tests {
         assert_eq!(count, 1);
     }
 
+    #[test]
+    fn tool_uses_record_mcp_server_and_tool() {
+        let conn = mem_db();
+        insert_tool_use(&conn, "t1", "s1", "mcp__claude_ai_Linear__list_issues", "ts", "ts", "/p", "{}").unwrap();
+        update_tool_use_response(&conn, "t2", "s1", "mcp__github__get_pr", "ts", "ts", "/p", "{}", "").unwrap();
+        insert_tool_use(&conn, "t3", "s1", "Read", "ts", "ts", "/p", "{}").unwrap();
+        let parts = |id: &str| -> (Option<String>, Option<String>) {
+            conn.query_row(
+                "SELECT mcp_server, mcp_tool FROM tool_uses WHERE tool_use_id = ?1",
+                params![id],
+                |r| Ok((r.get(0)?, r.get(1)?)),
+            )
+            .unwrap()
+        };
+        assert_eq!(parts("t1"), (Some("claude_ai_Linear".into()), Some("list_issues".into())));
+        assert_eq!(parts("t2"), (Some("github".into()), Some("get_pr".into())));
+        assert_eq!(parts("t3"), (None, None));
+    }
+
+    #[test]
+    fn init_db_backfills_mcp_columns() {
+        let conn = mem_db();
+        // A database from before the columns existed
+        conn.execute_batch("ALTER TABLE tool_uses DROP COLUMN mcp_server; ALTER TABLE tool_uses DROP COLUMN mcp_tool;")
+            .unwrap();
+        conn.execute(
+            "INSERT INTO tool_uses (tool_use_id, tool_name) VALUES ('t1', 'mcp__playwright__browser_click'), ('t2', 'Bash')",
+            [],
+        )
+        .unwrap();
+        init_db(&conn).unwrap();
+        let rows: Vec<(Option<String>, Option<String>)> = conn
+            .prepare("SELECT mcp_server, mcp_tool FROM tool_uses ORDER BY tool_use_id")
+            .unwrap()
+            .query_map([], |r| Ok((r.get(0)?, r.get(1)?)))
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(rows, vec![(Some("playwright".into()), Some("browser_click".into())), (None, None)]);
+
+        // Later opens leave the columns alone
+        conn.execute("UPDATE tool_uses SET mcp_server = NULL, mcp_tool = NULL", []).unwrap();
+        init_db(&conn).unwrap();
+        let backfilled: i64 =
+            conn.query_row("SELECT COUNT(*) FROM tool_uses WHERE mcp_server IS NOT NULL", [], |r| r.get(0)).unwrap();
+        assert_eq!(backfilled, 0);
+    }
+
+    #[test]
+    fn init_db_backfills_context_tokens_once() {
+        let conn = mem_db();
+        // A database from before the column existed
+        conn.execute_batch("ALTER TABLE api_calls DROP COLUMN context_tokens;").unwrap();
+        conn.execute(
+            "INSERT INTO api_calls (session_id, message_id, input_tokens, cache_creation_tokens, cache_read_tokens, output_tokens)
+             VALUES ('s1', 'm1', 10, 20, 300, 5)",
+            [],
+        )
+        .unwrap();
+        init_db(&conn).unwrap();
+        let context = || -> Option<i64> { conn.query_row("SELECT context_tokens FROM api_calls", [], |r| r.get(0)).unwrap() };
+        assert_eq!(context(), Some(330));
+
+        // Later opens leave the column alone
+        conn.execute("UPDATE api_calls SET context_tokens = NULL", []).unwrap();
+        init_db(&conn).unwrap();
+        assert_eq!(context(), None);
+    }
+
     #[test]
     fn insert_plan_basic() {
         let conn = mem_db();
@@ -692,4 +2194,256 @@ mod tests {
         assert!(ids.contains("toolu_a"));
         assert!(ids.contains("toolu_b"));
     }
+
+    #[test]
+    fn insert_notification_basic() {
+        let conn = mem_db();
+        insert_notification(&conn, "s1", "ts1", "permission_prompt", "Claude needs your permission to use Bash").unwrap();
+        let (kind, msg): (String, String) = conn
+            .query_row(
+                "SELECT notification_type, message FROM notifications WHERE session_id='s1'",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .unwrap();
+        assert_eq!(kind, "permission_prompt");
+        assert_eq!(msg, "Claude needs your permission to use Bash");
+    }
+
+    #[test]
+    fn insert_subagent_stop_basic() {
+        let conn = mem_db();
+        insert_subagent_stop(&conn, "s1", "ts1").unwrap();
+        insert_subagent_stop(&conn, "s1", "ts2").unwrap();
+        let count: i64 = conn
+            .query_row("SELECT COUNT(*) FROM subagent_stops WHERE session_id='s1'", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(count, 2);
+    }
+
+    #[test]
+    fn insert_compaction_basic() {
+        let conn = mem_db();
+        insert_compaction(&conn, "s1", "ts1", "auto", "").unwrap();
+        let (trigger, instructions): (String, String) = conn
+            .query_row(
+                "SELECT trigger, custom_instructions FROM compactions WHERE session_id='s1'",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .unwrap();
+        assert_eq!(trigger, "auto");
+        assert_eq!(instructions, "");
+    }
+
+    #[test]
+    fn insert_event_sequence_is_monotonic() {
+        let conn = mem_db();
+        let a = insert_event(&conn, "ts1", "SessionStart", "s1", r#"{"a":1}"#).unwrap();
+        let b = insert_event(&conn, "ts2", "Stop", "s1", r#"{"b":2}"#).unwrap();
+        assert!(b > a);
+
+        let (name, raw): (String, String) = conn
+            .query_row("SELECT event_name, raw_json FROM events WHERE seq = ?1", params![b], |row| {
+                Ok((row.get(0)?, row.get(1)?))
+            })
+            .unwrap();
+        assert_eq!(name, "Stop");
+        assert_eq!(raw, r#"{"b":2}"#);
+    }
+
+    #[test]
+    fn tool_use_duration_from_pre_and_post() {
+        let conn = mem_db();
+        insert_tool_use(&conn, "tu1", "s1", "Bash", "2026-02-27T00:00:01Z", "2026-02-27T00:00:01.250Z", "/p", "{}").unwrap();
+        update_tool_use_response(&conn, "tu1", "s1", "Bash", "2026-02-27T00:00:03Z", "2026-02-27T00:00:03.500Z", "/p", "{}", "ok").unwrap();
+        let (started, completed, duration): (String, String, i64) = conn
+            .query_row(
+                "SELECT started_at, completed_at, duration_ms FROM tool_uses WHERE tool_use_id='tu1'",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+            )
+            .unwrap();
+        assert_eq!(started, "2026-02-27T00:00:01.250Z");
+        assert_eq!(completed, "2026-02-27T00:00:03.500Z");
+        assert_eq!(duration, 2250);
+    }
+
+    #[test]
+    fn tool_use_duration_null_without_pre() {
+        let conn = mem_db();
+        update_tool_use_response(&conn, "tu1", "s1", "Bash", "ts", "2026-02-27T00:00:03.500Z", "/p", "{}", "ok").unwrap();
+        let duration: Option<i64> = conn
+            .query_row("SELECT duration_ms FROM tool_uses WHERE tool_use_id='tu1'", [], |row| row.get(0))
+            .unwrap();
+        assert!(duration.is_none());
+    }
+
+    #[test]
+    fn tool_response_roundtrip_identity() {
+        let conn = mem_db();
+        insert_tool_response(&conn, "tu1", "full output", 11, false, false).unwrap();
+        assert_eq!(get_tool_response(&conn, "tu1").unwrap(), Some(("full output".to_string(), false)));
+        let encoding: String = conn
+            .query_row("SELECT encoding FROM tool_responses WHERE tool_use_id='tu1'", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(encoding, "identity");
+    }
+
+    #[test]
+    fn tool_response_roundtrip_compressed() {
+        let conn = mem_db();
+        let text = "line of output\n".repeat(1000);
+        insert_tool_response(&conn, "tu1", &text, 20_000, true, true).unwrap();
+        let body: Vec<u8> = conn
+            .query_row("SELECT body FROM tool_responses WHERE tool_use_id='tu1'", [], |row| row.get(0))
+            .unwrap();
+        assert!(is_zstd(&body));
+        assert!(body.len() < text.len());
+        assert_eq!(get_tool_response(&conn, "tu1").unwrap(), Some((text, true)));
+    }
+
+    #[test]
+    fn tool_response_missing() {
+        let conn = mem_db();
+        assert!(get_tool_response(&conn, "nope").unwrap().is_none());
+    }
+
+    fn api_call(id: &str, model: &str, input: i64, output: i64) -> ApiCall {
+        ApiCall {
+            message_id: id.to_string(),
+            model: model.to_string(),
+            input_tokens: input,
+            output_tokens: output,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn upsert_api_call_is_idempotent() {
+        let conn = mem_db();
+        upsert_api_call(&conn, "s1", &api_call("msg_1", "m", 100, 5)).unwrap();
+        let mut later = api_call("msg_1", "m", 100, 40);
+        later.stop_reason = Some("end_turn".to_string());
+        upsert_api_call(&conn, "s1", &later).unwrap();
+        upsert_api_call(&conn, "s1", &later).unwrap();
+
+        let (count, output, stop): (i64, i64, String) = conn
+            .query_row("SELECT COUNT(*), MAX(output_tokens), MAX(stop_reason) FROM api_calls", [], |row| {
+                Ok((row.get(0)?, row.get(1)?, row.get(2)?))
+            })
+            .unwrap();
+        assert_eq!((count, output, stop.as_str()), (1, 40, "end_turn"));
+    }
+
+    #[test]
+    fn session_api_totals() {
+        let conn = mem_db();
+        assert!(!has_api_calls(&conn, "s1").unwrap());
+        let mut first = api_call("msg_1", "opus", 100, 10);
+        first.timestamp = Some("2026-02-27T10:00:00Z".to_string());
+        let mut second = api_call("msg_2", "sonnet", 50, 5);
+        second.timestamp = Some("2026-02-27T10:01:00Z".to_string());
+        upsert_api_call(&conn, "s1", &second).unwrap();
+        upsert_api_call(&conn, "s1", &first).unwrap();
+        upsert_api_call(&conn, "s2", &api_call("msg_3", "haiku", 1, 1)).unwrap();
+
+        assert!(has_api_calls(&conn, "s1").unwrap());
+        let totals = get_session_api_totals(&conn, "s1").unwrap();
+        assert_eq!(totals.model, "opus");
+        assert_eq!(totals.input_tokens, 150);
+        assert_eq!(totals.output_tokens, 15);
+        assert_eq!(totals.api_call_count, 2);
+
+        delete_api_calls(&conn, "s1").unwrap();
+        assert_eq!(get_session_api_totals(&conn, "s1").unwrap().api_call_count, 0);
+        assert!(has_api_calls(&conn, "s2").unwrap());
+    }
+
+    #[test]
+    fn link_sessions_ignores_self_and_duplicates() {
+        let conn = mem_db();
+        link_sessions(&conn, "b", "a", "clear", "t1").unwrap();
+        link_sessions(&conn, "b", "a", "resume", "t2").unwrap();
+        link_sessions(&conn, "b", "b", "resume", "t2").unwrap();
+        link_sessions(&conn, "b", "", "resume", "t2").unwrap();
+        let rows: Vec<(String, String)> = conn
+            .prepare("SELECT previous_session_id, reason FROM session_links")
+            .unwrap()
+            .query_map([], |r| Ok((r.get(0)?, r.get(1)?)))
+            .unwrap()
+            .map(|r| r.unwrap())
+            .collect();
+        assert_eq!(rows, vec![("a".to_string(), "clear".to_string())]);
+    }
+
+    #[test]
+    fn find_previous_session_by_reason() {
+        let conn = mem_db();
+        insert_session_start(&conn, "a", "2026-01-01T10:00:00Z", "startup", "/p", "/t/a").unwrap();
+        update_session_end(&conn, "a", "2026-01-01T10:10:00Z", "clear").unwrap();
+        insert_session_start(&conn, "b", "2026-01-01T10:20:00Z", "startup", "/p", "/t/b").unwrap();
+        insert_session_start(&conn, "c", "2026-01-01T10:30:00Z", "startup", "/other", "/t/c").unwrap();
+
+        let find = |reason: &str, cwd: &str, transcript: &str| {
+            find_previous_session(&conn, "new", reason, cwd, transcript).unwrap()
+        };
+        assert_eq!(find("startup", "/p", "/t/b"), Some(("b".to_string(), "transcript".to_string())));
+        assert_eq!(find("clear", "/p", "/t/new"), Some(("a".to_string(), "clear".to_string())));
+        assert_eq!(find("compact", "/p", "/t/new"), Some(("b".to_string(), "compact".to_string())));
+        assert_eq!(find("startup", "/p", "/t/new"), None);
+        assert_eq!(find("clear", "/empty", "/t/new"), None);
+    }
+
+    #[test]
+    fn session_chain_follows_links_both_ways() {
+        let conn = mem_db();
+        for (id, started) in [("a", "T1"), ("b", "T2"), ("c", "T3"), ("x", "T4")] {
+            insert_session_start(&conn, id, started, "startup", "/p", "").unwrap();
+        }
+        link_sessions(&conn, "b", "a", "clear", "T2").unwrap();
+        link_sessions(&conn, "c", "b", "resume", "T3").unwrap();
+        assert_eq!(get_session_chain(&conn, "b").unwrap(), vec!["a", "b", "c"]);
+        assert_eq!(get_session_chain(&conn, "c").unwrap(), vec!["a", "b", "c"]);
+        assert_eq!(get_session_chain(&conn, "x").unwrap(), vec!["x"]);
+    }
+
+    #[test]
+    fn context_usage_per_call_and_compaction() {
+        let conn = mem_db();
+        let call = |id: &str, ts: &str, input: i64, cache_read: i64| ApiCall {
+            message_id: id.to_string(),
+            timestamp: Some(ts.to_string()),
+            model: "claude-sonnet-4-5".to_string(),
+            input_tokens: input,
+            cache_read_tokens: cache_read,
+            ..Default::default()
+        };
+        upsert_api_call(&conn, "s1", &call("m1", "2026-01-01T10:00:00.000Z", 10, 100_000)).unwrap();
+        upsert_api_call(&conn, "s1", &call("m2", "2026-01-01T10:05:00.000Z", 10, 170_000)).unwrap();
+        upsert_api_call(&conn, "s1", &call("m3", "2026-01-01T10:10:00.000Z", 10, 20_000)).unwrap();
+        // A re-read line with larger usage raises the context size too
+        upsert_api_call(&conn, "s1", &call("m1", "2026-01-01T10:00:00.000Z", 20, 100_000)).unwrap();
+        insert_compaction(&conn, "s1", "2026-01-01T10:06:00Z", "auto", "").unwrap();
+        assert_eq!(get_sessions_without_context_limits(&conn).unwrap(), vec!["s1"]);
+
+        update_context_usage(&conn, "s1").unwrap();
+        let rows: Vec<(i64, i64, bool)> = conn
+            .prepare("SELECT context_tokens, context_limit, context_high FROM api_calls ORDER BY message_id")
+            .unwrap()
+            .query_map([], |r| Ok((r.get(0)?, r.get(1)?, r.get(2)?)))
+            .unwrap()
+            .map(|r| r.unwrap())
+            .collect();
+        assert_eq!(rows, vec![(100_020, 200_000, false), (170_010, 200_000, true), (20_010, 200_000, false)]);
+        let before: i64 = conn.query_row("SELECT context_tokens FROM compactions", [], |r| r.get(0)).unwrap();
+        assert_eq!(before, 170_010);
+        assert!(get_sessions_without_context_limits(&conn).unwrap().is_empty());
+
+        // A call past the default window means the session ran with the 1M window
+        upsert_api_call(&conn, "s1", &call("m4", "2026-01-01T10:20:00.000Z", 10, 300_000)).unwrap();
+        update_context_usage(&conn, "s1").unwrap();
+        let high: i64 = conn.query_row("SELECT SUM(context_high) FROM api_calls", [], |r| r.get(0)).unwrap();
+        assert_eq!(high, 0);
+    }
 }
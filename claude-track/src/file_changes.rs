@@ -0,0 +1,176 @@
+use std::path::Path;
+
+use serde_json::Value;
+
+use crate::models::FileChange;
+
+/// File reads and changes made by one successful tool call. Line counts
+/// come from the tool input: `content` for Write, `old_string` and
+/// `new_string` for Edit and MultiEdit, `new_source` for NotebookEdit. A
+/// Write reported as an update counts as an edit, with the lines of its
+/// structured patch when the response has one. `rm` and `git rm` commands
+/// count as deletes. When `count_lines` is false (content was hashed or
+/// dropped), line counts are left unknown.
+pub fn changes(tool_name: &str, input: &Value, response: &Value, cwd: &str, count_lines: bool) -> Vec<FileChange> {
+    let str_field = |v: &Value, k: &str| v.get(k).and_then(|s| s.as_str()).unwrap_or_default().to_string();
+    let path = input
+        .get("file_path")
+        .or_else(|| input.get("notebook_path"))
+        .and_then(|p| p.as_str())
+        .map(String::from);
+    let change = |operation: &str, added: i64, removed: i64| FileChange {
+        path: path.clone().unwrap_or_default(),
+        operation: operation.to_string(),
+        lines_added: count_lines.then_some(added),
+        lines_removed: count_lines.then_some(removed),
+    };
+
+    let changes = match tool_name {
+        "Read" => vec![FileChange {
+            lines_added: Some(0),
+            lines_removed: Some(0),
+            ..change("read", 0, 0)
+        }],
+        "Write" => {
+            let content = str_field(input, "content");
+            if response.get("type").and_then(|t| t.as_str()) == Some("update") {
+                let (added, removed) = patch_lines(response).unwrap_or((line_count(&content), 0));
+                vec![change("edit", added, removed)]
+            } else {
+                vec![change("create", line_count(&content), 0)]
+            }
+        }
+        "Edit" => vec![change(
+            "edit",
+            line_count(&str_field(input, "new_string")),
+            line_count(&str_field(input, "old_string")),
+        )],
+        "MultiEdit" => {
+            let edits = input.get("edits").and_then(|e| e.as_array()).cloned().unwrap_or_default();
+            let added = edits.iter().map(|e| line_count(&str_field(e, "new_string"))).sum();
+            let removed = edits.iter().map(|e| line_count(&str_field(e, "old_string"))).sum();
+            vec![change("edit", added, removed)]
+        }
+        "NotebookEdit" => {
+            let added = match input.get("edit_mode").and_then(|m| m.as_str()) {
+                Some("delete") => 0,
+                _ => line_count(&str_field(input, "new_source")),
+            };
+            vec![change("edit", added, 0)]
+        }
+        "Bash" => removed_paths(&str_field(input, "command"), cwd)
+            .into_iter()
+            .map(|path| FileChange {
+                path,
+                operation: "delete".to_string(),
+                lines_added: None,
+                lines_removed: None,
+            })
+            .collect(),
+        _ => Vec::new(),
+    };
+    changes.into_iter().filter(|c| !c.path.is_empty()).collect()
+}
+
+fn line_count(text: &str) -> i64 {
+    text.lines().count() as i64
+}
+
+/// Added and removed lines in a Write response's `structuredPatch` hunks.
+fn patch_lines(response: &Value) -> Option<(i64, i64)> {
+    let hunks = response.get("structuredPatch")?.as_array()?;
+    let lines: Vec<&str> = hunks
+        .iter()
+        .filter_map(|h| h.get("lines").and_then(|l| l.as_array()))
+        .flatten()
+        .filter_map(|l| l.as_str())
+        .collect();
+    let added = lines.iter().filter(|l| l.starts_with('+')).count() as i64;
+    let removed = lines.iter().filter(|l| l.starts_with('-')).count() as i64;
+    Some((added, removed))
+}
+
+/// Paths removed by a plain `rm` or `git rm` command, made absolute against
+/// `cwd`. Commands combined with `;`, `&&`, pipes or globs are skipped rather
+/// than guessed at.
+fn removed_paths(command: &str, cwd: &str) -> Vec<String> {
+    if command.contains(|c| ";&|<>*?$`(".contains(c)) {
+        return Vec::new();
+    }
+    let words: Vec<&str> = command.split_whitespace().collect();
+    let args = match words.as_slice() {
+        ["rm", args @ ..] | ["git", "rm", args @ ..] => args,
+        _ => return Vec::new(),
+    };
+    args.iter()
+        .filter(|a| !a.starts_with('-'))
+        .map(|a| a.trim_matches(|c| c == '\'' || c == '"'))
+        .map(|a| Path::new(cwd).join(a).to_string_lossy().to_string())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn one(tool: &str, input: Value, response: Value) -> FileChange {
+        let mut changes = changes(tool, &input, &response, "/repo", true);
+        assert_eq!(changes.len(), 1);
+        changes.remove(0)
+    }
+
+    #[test]
+    fn write_create_and_update() {
+        let created = one("Write", json!({"file_path": "/repo/a.rs", "content": "a\nb\n"}), json!({"type": "create"}));
+        assert_eq!((created.operation.as_str(), created.lines_added, created.lines_removed), ("create", Some(2), Some(0)));
+
+        let patch = json!({"type": "update", "structuredPatch": [{"lines": [" a", "-b", "+c", "+d"]}]});
+        let updated = one("Write", json!({"file_path": "/repo/a.rs", "content": "a\nc\nd\n"}), patch);
+        assert_eq!((updated.operation.as_str(), updated.lines_added, updated.lines_removed), ("edit", Some(2), Some(1)));
+    }
+
+    #[test]
+    fn edits_count_old_and_new_lines() {
+        let edit = one(
+            "Edit",
+            json!({"file_path": "/repo/a.rs", "old_string": "x", "new_string": "y\nz"}),
+            Value::Null,
+        );
+        assert_eq!((edit.lines_added, edit.lines_removed), (Some(2), Some(1)));
+
+        let multi = one(
+            "MultiEdit",
+            json!({"file_path": "/repo/a.rs", "edits": [{"old_string": "a\nb", "new_string": "c"}, {"old_string": "d", "new_string": "e\nf\ng"}]}),
+            Value::Null,
+        );
+        assert_eq!((multi.lines_added, multi.lines_removed), (Some(4), Some(3)));
+
+        let notebook = one(
+            "NotebookEdit",
+            json!({"notebook_path": "/repo/n.ipynb", "new_source": "print(1)", "edit_mode": "insert"}),
+            Value::Null,
+        );
+        assert_eq!((notebook.path.as_str(), notebook.lines_added), ("/repo/n.ipynb", Some(1)));
+    }
+
+    #[test]
+    fn read_and_unknown_line_counts() {
+        let read = one("Read", json!({"file_path": "/repo/a.rs"}), json!("..."));
+        assert_eq!(read.operation, "read");
+        let hashed = changes("Edit", &json!({"file_path": "sha256:x", "old_string": "o", "new_string": "n"}), &Value::Null, "/", false);
+        assert_eq!((hashed[0].lines_added, hashed[0].lines_removed), (None, None));
+        assert!(changes("Grep", &json!({"pattern": "x"}), &Value::Null, "/", true).is_empty());
+    }
+
+    #[test]
+    fn rm_commands_are_deletes() {
+        let deleted = changes("Bash", &json!({"command": "rm -f old.rs /tmp/x"}), &Value::Null, "/repo", true);
+        let paths: Vec<&str> = deleted.iter().map(|c| c.path.as_str()).collect();
+        assert_eq!(paths, vec!["/repo/old.rs", "/tmp/x"]);
+        assert_eq!(deleted[0].operation, "delete");
+        assert_eq!(changes("Bash", &json!({"command": "git rm a.rs"}), &Value::Null, "/repo", true).len(), 1);
+        assert!(changes("Bash", &json!({"command": "rm *.log"}), &Value::Null, "/repo", true).is_empty());
+        assert!(changes("Bash", &json!({"command": "cargo rm x && rm y"}), &Value::Null, "/repo", true).is_empty());
+    }
+}
@@ -1,6 +1,19 @@
+mod archive;
+mod attribution;
+mod bash_command;
+mod capture;
 mod commands;
+mod config;
+mod context;
 mod db;
+mod file_changes;
+mod git;
 mod models;
+mod redact;
+mod tool_result;
+mod web;
+
+use std::path::PathBuf;
 
 use clap::{Parser, Subcommand};
 
@@ -18,9 +31,24 @@ enum Commands {
     /// Show usage statistics
     Stats,
     /// Register all hooks in Claude Code settings
-    Install,
+    Install {
+        /// Instead, install git hooks in the current repository that link commits to sessions
+        #[arg(long)]
+        git: bool,
+    },
     /// Remove all hooks and optionally delete data
-    Uninstall,
+    Uninstall {
+        /// Instead, remove the git hooks from the current repository
+        #[arg(long)]
+        git: bool,
+    },
+    /// Git hook entrypoint — called by the hooks `install --git` adds
+    GitHook {
+        /// Name of the git hook (prepare-commit-msg or post-commit)
+        name: String,
+        /// Arguments git passed to the hook
+        args: Vec<String>,
+    },
     /// Import legacy JSONL data into SQLite
     Migrate,
     /// Backfill plans from historical transcript files
@@ -30,6 +58,38 @@ enum Commands {
         /// The SQL query to execute
         sql: String,
     },
+    /// Show the prompts, tool calls and answers of one session
+    Show {
+        /// Session id, or a unique prefix of one
+        session: String,
+        /// Print stored full tool responses instead of summaries
+        #[arg(long)]
+        full: bool,
+        /// Print every session in the conversation: sessions resumed from,
+        /// cleared or compacted into, or sharing a transcript with this one
+        #[arg(long)]
+        chain: bool,
+    },
+    /// Export session metadata, transcripts and prompts to the claude-track/sessions branch
+    Checkpoint {
+        /// Only export this session (default: every session recorded in the current repository)
+        #[arg(long)]
+        session: Option<String>,
+    },
+    /// Scrub secrets from already-stored prompts, tool inputs, responses and events
+    Redact,
+    /// Rebuild the database by replaying the raw event log
+    Replay {
+        /// Only replay events received at or after this time (e.g. 2026-02-01)
+        #[arg(long)]
+        since: Option<String>,
+        /// Only replay events from this session
+        #[arg(long)]
+        session: Option<String>,
+        /// Write to a new database at this path instead of rebuilding in place
+        #[arg(long)]
+        into: Option<PathBuf>,
+    },
 }
 
 fn main() {
@@ -38,10 +98,19 @@ fn main() {
     match cli.command {
         Commands::Hook => commands::hook::run(),
         Commands::Stats => commands::stats::run(),
-        Commands::Install => commands::install::run(),
-        Commands::Uninstall => commands::uninstall::run(),
+        Commands::Install { git } => commands::install::run(git),
+        Commands::Uninstall { git } => commands::uninstall::run(git),
+        Commands::GitHook { ref name, ref args } => commands::git_hook::run(name, args),
         Commands::Migrate => commands::migrate::run(),
         Commands::Backfill => commands::backfill::run(),
         Commands::Query { ref sql } => commands::query::run(sql),
+        Commands::Show { ref session, full, chain } => commands::show::run(session, full, chain),
+        Commands::Checkpoint { ref session } => commands::checkpoint::run(session.as_deref()),
+        Commands::Redact => commands::redact::run(),
+        Commands::Replay {
+            ref since,
+            ref session,
+            ref into,
+        } => commands::replay::run(since.as_deref(), session.as_deref(), into.as_deref()),
     }
 }
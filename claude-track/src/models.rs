@@ -15,19 +15,107 @@ pub struct HookInput {
     pub tool_input: Option<serde_json::Value>,
     pub tool_response: Option<serde_json::Value>,
 
-    // Session lifecycle
+    // Session lifecycle. SessionStart sends its reason as `source`.
+    #[serde(alias = "source")]
     pub reason: Option<String>,
 
     // UserPromptSubmit
     pub prompt: Option<String>,
 
     // Stop event
-    #[allow(dead_code)]
     pub last_assistant_message: Option<String>,
 
-    // Stop event — may contain a stop_hook_active flag
-    #[allow(dead_code)]
+    // Stop event — true when Claude is continuing because a Stop hook blocked
     pub stop_hook_active: Option<bool>,
+
+    // Notification
+    pub message: Option<String>,
+    pub notification_type: Option<String>,
+
+    // PreCompact ("manual" or "auto"); GitSnapshot (the event that took it)
+    pub trigger: Option<String>,
+    pub custom_instructions: Option<String>,
+
+    // GitSnapshot
+    pub git: Option<GitContext>,
+
+    // GitCommit
+    pub commit: Option<GitCommit>,
+
+    // Added by claude-track when content was hashed or dropped ("hashed" or "none")
+    pub capture_level: Option<String>,
+}
+
+/// Git state of a session's working directory, recorded at SessionStart and
+/// at each Stop.
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GitContext {
+    /// `None` when HEAD is detached.
+    pub branch: Option<String>,
+    /// `None` before the first commit.
+    pub head_sha: Option<String>,
+    /// Modified and staged paths, as listed by `git status` (untracked files
+    /// aren't counted). `None` when `git status` took too long.
+    pub dirty_files: Option<i64>,
+    pub remote_url: Option<String>,
+}
+
+/// A commit recorded by the `post-commit` git hook.
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GitCommit {
+    pub sha: String,
+    pub branch: Option<String>,
+    pub committed_at: String,
+    pub message: String,
+    pub files: Vec<CommitFile>,
+    #[serde(default)]
+    pub attribution: Option<CommitAttribution>,
+}
+
+/// Who wrote a commit's changed lines, judged against the agent's recent
+/// Write/Edit/MultiEdit calls.
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CommitAttribution {
+    pub agent_lines: i64,
+    pub human_added: i64,
+    pub human_removed: i64,
+    /// Share of added lines that are agent-authored, 0–100.
+    pub agent_percentage: f64,
+}
+
+/// Lines changed in one file of a commit. Counts are `None` for binary files.
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CommitFile {
+    pub path: String,
+    pub additions: Option<i64>,
+    pub deletions: Option<i64>,
+}
+
+/// One simple command from a Bash tool call, e.g. the `cargo test` in
+/// `cd api && cargo test -- foo`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BashCommand {
+    /// Index among the commands of the call, from 0.
+    pub position: i64,
+    /// Program name without its directory.
+    pub program: String,
+    /// `commit` for git, `run build` for npm, `-m pytest` for python.
+    pub subcommand: Option<String>,
+    /// Program and subcommand, e.g. `cargo test`.
+    pub command: String,
+    /// One of test, build, vcs, package, filesystem or network.
+    pub category: Option<String>,
+}
+
+/// A file read or changed by one tool call. Line counts are `None` when the
+/// content wasn't captured or, for deletes, isn't known.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FileChange {
+    pub path: String,
+    /// `read`, `create`, `edit` or `delete`
+    pub operation: String,
+    pub lines_added: Option<i64>,
+    pub lines_removed: Option<i64>,
 }
 
 /// A single tool-call record persisted to the JSONL log (legacy format).
@@ -46,13 +134,30 @@ pub struct TranscriptLine {
     #[serde(rename = "type")]
     pub line_type: Option<String>,
     pub message: Option<TranscriptMessage>,
+    pub timestamp: Option<String>,
+    #[serde(rename = "requestId")]
+    pub request_id: Option<String>,
+    pub uuid: Option<String>,
+    #[serde(rename = "parentUuid")]
+    pub parent_uuid: Option<String>,
+    /// True for lines written by a subagent rather than the main conversation.
+    #[serde(rename = "isSidechain")]
+    pub is_sidechain: Option<bool>,
+    /// The session that wrote the line. A resumed session's transcript starts
+    /// with lines carrying the id of the session it resumed.
+    #[serde(rename = "sessionId")]
+    pub session_id: Option<String>,
 }
 
 /// The message field inside a transcript line.
 #[derive(Debug, Deserialize)]
 pub struct TranscriptMessage {
+    pub id: Option<String>,
     pub model: Option<String>,
+    pub stop_reason: Option<String>,
     pub usage: Option<TranscriptUsage>,
+    /// A string, or an array of content blocks (text, tool_use, tool_result, ...).
+    pub content: Option<serde_json::Value>,
 }
 
 /// Token usage from a transcript message.
@@ -64,6 +169,58 @@ pub struct TranscriptUsage {
     pub cache_read_input_tokens: Option<i64>,
 }
 
+/// One API call, folded from every transcript line that shares its message id
+/// (Claude Code writes a line per content block, each repeating the usage).
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct ApiCall {
+    /// `message.id`, else `requestId`, else `line:<byte offset>` for old transcripts without either.
+    pub message_id: String,
+    pub request_id: Option<String>,
+    pub timestamp: Option<String>,
+    pub model: String,
+    pub stop_reason: Option<String>,
+    pub input_tokens: i64,
+    pub cache_creation_tokens: i64,
+    pub cache_read_tokens: i64,
+    pub output_tokens: i64,
+    /// Byte offset of the message's first line in the transcript.
+    pub transcript_offset: u64,
+}
+
+/// A subagent (sidechain) transcript line, reduced to what's needed to
+/// attribute it to the Task tool call that spawned the subagent.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct SidechainLine {
+    pub uuid: String,
+    pub parent_uuid: Option<String>,
+    /// For the chain's root line: the prompt the subagent was given.
+    pub prompt: Option<String>,
+    /// The `ApiCall::message_id` this line contributed to, if any.
+    pub message_id: Option<String>,
+    /// Ids of tool_use blocks in this line.
+    pub tool_use_ids: Vec<String>,
+}
+
+/// A tool_result block from a transcript user line.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct ToolResult {
+    pub tool_use_id: String,
+    pub is_error: bool,
+    pub text: String,
+}
+
+/// Everything read from one incremental pass over a transcript.
+#[derive(Debug, Default)]
+pub struct TranscriptChunk {
+    pub calls: Vec<ApiCall>,
+    pub sidechain: Vec<SidechainLine>,
+    pub tool_results: Vec<ToolResult>,
+    /// Distinct `sessionId`s on the lines read, in order of first appearance.
+    pub session_ids: Vec<String>,
+    /// Byte position after the last complete line.
+    pub offset: u64,
+}
+
 /// Aggregated token usage from a transcript.
 #[derive(Debug, Default)]
 pub struct AggregatedTokenUsage {
@@ -118,6 +275,13 @@ mod tests {
         assert_eq!(input.transcript_path.unwrap(), "/tmp/t.jsonl");
     }
 
+    #[test]
+    fn session_start_source_is_reason() {
+        let json = r#"{"hook_event_name":"SessionStart","session_id":"s1","source":"clear"}"#;
+        let input: HookInput = serde_json::from_str(json).unwrap();
+        assert_eq!(input.reason.unwrap(), "clear");
+    }
+
     #[test]
     fn hook_input_session_end() {
         let json = r#"{"hook_event_name":"SessionEnd","session_id":"s1","reason":"logout"}"#;
@@ -155,6 +319,23 @@ mod tests {
         assert_eq!(input.tool_response.unwrap(), "file contents");
     }
 
+    #[test]
+    fn hook_input_notification() {
+        let json = r#"{"hook_event_name":"Notification","session_id":"s1","message":"Claude needs your permission to use Bash"}"#;
+        let input: HookInput = serde_json::from_str(json).unwrap();
+        assert_eq!(input.hook_event_name.unwrap(), "Notification");
+        assert_eq!(input.message.unwrap(), "Claude needs your permission to use Bash");
+        assert!(input.notification_type.is_none());
+    }
+
+    #[test]
+    fn hook_input_pre_compact() {
+        let json = r#"{"hook_event_name":"PreCompact","session_id":"s1","trigger":"manual","custom_instructions":"keep the API notes"}"#;
+        let input: HookInput = serde_json::from_str(json).unwrap();
+        assert_eq!(input.trigger.unwrap(), "manual");
+        assert_eq!(input.custom_instructions.unwrap(), "keep the API notes");
+    }
+
     #[test]
     fn hook_input_ignores_unknown_fields() {
         let json = r#"{"hook_event_name":"PostToolUse","unknown_field":"value","tool_name":"Read"}"#;
@@ -199,6 +380,27 @@ mod tests {
         assert_eq!(usage.cache_read_input_tokens.unwrap(), 300);
     }
 
+    #[test]
+    fn transcript_line_ids_and_stop_reason() {
+        let json = r#"{"type":"assistant","timestamp":"2026-02-27T10:00:00.000Z","requestId":"req_1","message":{"id":"msg_1","model":"m","stop_reason":"tool_use"}}"#;
+        let line: TranscriptLine = serde_json::from_str(json).unwrap();
+        assert_eq!(line.timestamp.unwrap(), "2026-02-27T10:00:00.000Z");
+        assert_eq!(line.request_id.unwrap(), "req_1");
+        let msg = line.message.unwrap();
+        assert_eq!(msg.id.unwrap(), "msg_1");
+        assert_eq!(msg.stop_reason.unwrap(), "tool_use");
+    }
+
+    #[test]
+    fn transcript_line_sidechain_fields() {
+        let json = r#"{"type":"user","uuid":"u2","parentUuid":"u1","isSidechain":true,"message":{"role":"user","content":[{"type":"text","text":"hi"}]}}"#;
+        let line: TranscriptLine = serde_json::from_str(json).unwrap();
+        assert_eq!(line.uuid.unwrap(), "u2");
+        assert_eq!(line.parent_uuid.unwrap(), "u1");
+        assert_eq!(line.is_sidechain, Some(true));
+        assert!(line.message.unwrap().content.unwrap().is_array());
+    }
+
     #[test]
     fn transcript_line_partial() {
         let json = r#"{"type":"user"}"#;
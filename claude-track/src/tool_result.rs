@@ -0,0 +1,320 @@
+use serde_json::Value;
+
+use crate::models::ToolResult;
+
+/// Max characters of error text kept per tool call.
+const MAX_ERROR_CHARS: usize = 500;
+
+/// How a tool call ended, derived from its PostToolUse `tool_response`.
+#[derive(Debug, PartialEq)]
+pub struct ToolOutcome {
+    /// One of "ok", "error", "interrupted" or "denied".
+    pub status: &'static str,
+    pub exit_code: Option<i64>,
+    pub error_text: Option<String>,
+}
+
+impl ToolOutcome {
+    fn ok() -> Self {
+        ToolOutcome { status: "ok", exit_code: None, error_text: None }
+    }
+
+    fn with_status(status: &'static str, error_text: Option<&str>) -> Self {
+        ToolOutcome {
+            status,
+            exit_code: None,
+            error_text: error_text.map(truncate_chars),
+        }
+    }
+}
+
+/// Classify a tool response. Responses come in two shapes: a plain string
+/// (most built-in tools, and the error message when a call is rejected) or an
+/// object with tool-specific fields such as Bash's `stdout`/`stderr`/`interrupted`.
+pub fn classify(tool_name: &str, response: &Value) -> ToolOutcome {
+    match response {
+        Value::String(s) => classify_text(s),
+        Value::Object(_) => classify_object(tool_name, response),
+        // MCP tools return a list of content blocks
+        Value::Array(blocks) => blocks
+            .iter()
+            .filter_map(|b| b.get("text").and_then(|t| t.as_str()))
+            .map(classify_text)
+            .find(|o| o.status != "ok")
+            .unwrap_or_else(ToolOutcome::ok),
+        _ => ToolOutcome::ok(),
+    }
+}
+
+/// Classify a transcript `tool_result` block, for calls that never reached
+/// PostToolUse (Claude Code skips it for failed and denied calls).
+pub fn classify_result(result: &ToolResult) -> ToolOutcome {
+    if !result.is_error {
+        return classify_text(&result.text);
+    }
+    let text = Some(result.text.as_str()).filter(|t| !t.is_empty());
+    if text.is_some_and(is_denial) {
+        ToolOutcome::with_status("denied", text)
+    } else if result.text.starts_with("[Request interrupted") {
+        ToolOutcome::with_status("interrupted", None)
+    } else {
+        ToolOutcome::with_status("error", text)
+    }
+}
+
+fn classify_text(text: &str) -> ToolOutcome {
+    if is_denial(text) {
+        ToolOutcome::with_status("denied", Some(text))
+    } else if text.contains("<tool_use_error>") || text.starts_with("Error:") {
+        ToolOutcome::with_status("error", Some(text))
+    } else if text.starts_with("[Request interrupted") {
+        ToolOutcome::with_status("interrupted", None)
+    } else {
+        ToolOutcome::ok()
+    }
+}
+
+fn classify_object(tool_name: &str, response: &Value) -> ToolOutcome {
+    let str_field = |k: &str| response.get(k).and_then(|v| v.as_str()).filter(|s| !s.is_empty());
+    let exit_code = ["exit_code", "exitCode", "returncode"]
+        .iter()
+        .find_map(|k| response.get(*k).and_then(|v| v.as_i64()));
+    let stderr = str_field("stderr");
+
+    let mut outcome = if response.get("interrupted").and_then(|v| v.as_bool()) == Some(true) {
+        ToolOutcome::with_status("interrupted", stderr)
+    } else if response.get("is_error").and_then(|v| v.as_bool()) == Some(true) {
+        let text = str_field("error").or(str_field("content")).or(stderr);
+        if text.is_some_and(is_denial) {
+            ToolOutcome::with_status("denied", text)
+        } else {
+            ToolOutcome::with_status("error", text)
+        }
+    } else if let Some(err) = str_field("error") {
+        if is_denial(err) {
+            ToolOutcome::with_status("denied", Some(err))
+        } else {
+            ToolOutcome::with_status("error", Some(err))
+        }
+    } else if exit_code.is_some_and(|c| c != 0) {
+        ToolOutcome::with_status("error", stderr)
+    } else if tool_name == "Bash" && exit_code.is_none() && stderr.is_some() && str_field("stdout").is_none() {
+        // Bash without an exit code: stderr with no stdout is the best failure signal we get
+        ToolOutcome::with_status("error", stderr)
+    } else {
+        ToolOutcome::ok()
+    };
+    outcome.exit_code = exit_code;
+    outcome
+}
+
+/// How the user answered an ExitPlanMode call.
+#[derive(Debug, PartialEq)]
+pub struct PlanOutcome {
+    pub accepted: bool,
+    /// What the user typed when rejecting the plan, if anything.
+    pub feedback: Option<String>,
+}
+
+/// Read an ExitPlanMode result. Approval comes back as a normal result;
+/// rejecting the plan (keeping planning) comes back as a denial, with the
+/// user's feedback after "the user said:".
+pub fn plan_outcome(is_error: bool, text: &str) -> PlanOutcome {
+    if !is_error && !is_denial(text) {
+        return PlanOutcome {
+            accepted: true,
+            feedback: None,
+        };
+    }
+    let feedback = text
+        .split_once("the user said:")
+        .map(|(_, said)| said.trim().to_string())
+        .filter(|s| !s.is_empty());
+    PlanOutcome {
+        accepted: false,
+        feedback,
+    }
+}
+
+/// The tool_result blocks in a transcript message's content. A result's
+/// content is a string or a list of text blocks.
+pub fn tool_results(content: &Value) -> Vec<ToolResult> {
+    let Some(blocks) = content.as_array() else {
+        return Vec::new();
+    };
+    blocks
+        .iter()
+        .filter(|b| b.get("type").and_then(|t| t.as_str()) == Some("tool_result"))
+        .filter_map(|b| {
+            let text = match b.get("content") {
+                Some(Value::String(s)) => s.clone(),
+                Some(Value::Array(parts)) => parts
+                    .iter()
+                    .filter_map(|p| p.get("text").and_then(|t| t.as_str()))
+                    .collect::<Vec<_>>()
+                    .join("\n"),
+                _ => String::new(),
+            };
+            Some(ToolResult {
+                tool_use_id: b.get("tool_use_id")?.as_str()?.to_string(),
+                is_error: b.get("is_error").and_then(|e| e.as_bool()).unwrap_or(false),
+                text,
+            })
+        })
+        .collect()
+}
+
+/// Claude Code reports a rejected permission prompt with one of these messages.
+fn is_denial(text: &str) -> bool {
+    text.contains("doesn't want to proceed with this tool use")
+        || text.contains("tool use was rejected")
+        || (text.contains("Permission to use") && text.contains("has been denied"))
+}
+
+fn truncate_chars(s: &str) -> String {
+    if s.chars().count() > MAX_ERROR_CHARS {
+        let cut: String = s.chars().take(MAX_ERROR_CHARS - 3).collect();
+        format!("{cut}...")
+    } else {
+        s.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn plain_text_is_ok() {
+        assert_eq!(classify("Read", &json!("file contents")), ToolOutcome::ok());
+    }
+
+    #[test]
+    fn tool_use_error_text() {
+        let o = classify("Edit", &json!("<tool_use_error>String to replace not found</tool_use_error>"));
+        assert_eq!(o.status, "error");
+        assert!(o.error_text.unwrap().contains("String to replace not found"));
+    }
+
+    #[test]
+    fn denial_text() {
+        let o = classify(
+            "Bash",
+            &json!("The user doesn't want to proceed with this tool use. The tool use was rejected."),
+        );
+        assert_eq!(o.status, "denied");
+    }
+
+    #[test]
+    fn plan_outcomes() {
+        assert_eq!(
+            plan_outcome(false, "User has approved your plan. You can now start coding."),
+            PlanOutcome { accepted: true, feedback: None }
+        );
+        let rejected = plan_outcome(
+            true,
+            "The user doesn't want to proceed with this tool use. The tool use was rejected (eg. if it was a file \
+             edit, the new_string was NOT written to the file). To tell you how to proceed, the user said:\nAlso cover the CLI\n",
+        );
+        assert_eq!(
+            rejected,
+            PlanOutcome { accepted: false, feedback: Some("Also cover the CLI".into()) }
+        );
+        let plain = plan_outcome(true, "The user doesn't want to proceed with this tool use. STOP what you are doing.");
+        assert_eq!(plain, PlanOutcome { accepted: false, feedback: None });
+    }
+
+    #[test]
+    fn tool_results_from_content() {
+        let content = json!([
+            {"type": "tool_result", "tool_use_id": "a", "content": "done"},
+            {"type": "tool_result", "tool_use_id": "b", "is_error": true, "content": [{"type": "text", "text": "no"}]},
+            {"type": "text", "text": "ignored"}
+        ]);
+        let results = tool_results(&content);
+        assert_eq!(results.len(), 2);
+        assert_eq!((results[0].tool_use_id.as_str(), results[0].is_error, results[0].text.as_str()), ("a", false, "done"));
+        assert_eq!((results[1].tool_use_id.as_str(), results[1].is_error, results[1].text.as_str()), ("b", true, "no"));
+        assert!(tool_results(&json!("text")).is_empty());
+    }
+
+    #[test]
+    fn transcript_results() {
+        let result = |is_error: bool, text: &str| ToolResult { tool_use_id: "a".into(), is_error, text: text.into() };
+        assert_eq!(classify_result(&result(false, "done")), ToolOutcome::ok());
+        let o = classify_result(&result(true, "Exit code 1\nboom"));
+        assert_eq!(o.status, "error");
+        assert_eq!(o.error_text.as_deref(), Some("Exit code 1\nboom"));
+        assert_eq!(
+            classify_result(&result(true, "The user doesn't want to proceed with this tool use.")).status,
+            "denied"
+        );
+        assert_eq!(classify_result(&result(true, "[Request interrupted by user for tool use]")).status, "interrupted");
+    }
+
+    #[test]
+    fn bash_success() {
+        let o = classify("Bash", &json!({"stdout": "ok", "stderr": "", "interrupted": false}));
+        assert_eq!(o, ToolOutcome::ok());
+    }
+
+    #[test]
+    fn bash_nonzero_exit_code() {
+        let o = classify("Bash", &json!({"stdout": "", "stderr": "boom", "exit_code": 2, "interrupted": false}));
+        assert_eq!(o.status, "error");
+        assert_eq!(o.exit_code, Some(2));
+        assert_eq!(o.error_text.as_deref(), Some("boom"));
+    }
+
+    #[test]
+    fn bash_zero_exit_code_with_stderr_is_ok() {
+        let o = classify("Bash", &json!({"stdout": "", "stderr": "warning: x", "exitCode": 0}));
+        assert_eq!(o.status, "ok");
+        assert_eq!(o.exit_code, Some(0));
+    }
+
+    #[test]
+    fn bash_stderr_only_without_exit_code() {
+        let o = classify("Bash", &json!({"stdout": "", "stderr": "command not found", "interrupted": false}));
+        assert_eq!(o.status, "error");
+        assert!(o.exit_code.is_none());
+    }
+
+    #[test]
+    fn bash_interrupted() {
+        let o = classify("Bash", &json!({"stdout": "partial", "stderr": "", "interrupted": true}));
+        assert_eq!(o.status, "interrupted");
+    }
+
+    #[test]
+    fn is_error_flag() {
+        let o = classify("mcp__github__get_issue", &json!({"is_error": true, "content": "404 Not Found"}));
+        assert_eq!(o.status, "error");
+        assert_eq!(o.error_text.as_deref(), Some("404 Not Found"));
+    }
+
+    #[test]
+    fn error_field() {
+        let o = classify("WebFetch", &json!({"error": "timeout"}));
+        assert_eq!(o.status, "error");
+        assert_eq!(o.error_text.as_deref(), Some("timeout"));
+    }
+
+    #[test]
+    fn content_block_array() {
+        let o = classify("mcp__x__y", &json!([{"type": "text", "text": "Error: bad request"}]));
+        assert_eq!(o.status, "error");
+        let o = classify("mcp__x__y", &json!([{"type": "text", "text": "fine"}]));
+        assert_eq!(o.status, "ok");
+    }
+
+    #[test]
+    fn long_error_text_truncated_on_char_boundary() {
+        let long = format!("Error: {}", "é".repeat(600));
+        let o = classify("Bash", &json!(long));
+        let text = o.error_text.unwrap();
+        assert_eq!(text.chars().count(), MAX_ERROR_CHARS);
+        assert!(text.ends_with("..."));
+    }
+}
@@ -0,0 +1,40 @@
+/// Host of an absolute URL, lowercased and without credentials or port.
+/// None for anything that isn't an absolute URL, such as a hashed value.
+pub fn domain(url: &str) -> Option<String> {
+    let (scheme, rest) = url.split_once("://")?;
+    if scheme.is_empty() || !scheme.chars().all(|c| c.is_ascii_alphanumeric() || "+.-".contains(c)) {
+        return None;
+    }
+    let authority = rest.split(['/', '?', '#']).next()?;
+    let host = authority.rsplit('@').next()?;
+    let host = match host.strip_prefix('[') {
+        // IPv6 literal
+        Some(v6) => v6.split(']').next()?,
+        None => host.split(':').next()?,
+    };
+    if host.is_empty() {
+        return None;
+    }
+    Some(host.to_ascii_lowercase())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn domain_from_urls() {
+        assert_eq!(domain("https://Docs.RS/serde/latest/serde/").as_deref(), Some("docs.rs"));
+        assert_eq!(domain("http://user:pw@example.com:8080/a?b#c").as_deref(), Some("example.com"));
+        assert_eq!(domain("https://example.com?q=1").as_deref(), Some("example.com"));
+        assert_eq!(domain("http://[::1]:3000/").as_deref(), Some("::1"));
+    }
+
+    #[test]
+    fn domain_rejects_non_urls() {
+        assert_eq!(domain("example.com/path"), None);
+        assert_eq!(domain("sha256:abc:20"), None);
+        assert_eq!(domain("file:///etc/hosts"), None);
+        assert_eq!(domain(""), None);
+    }
+}
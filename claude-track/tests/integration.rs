@@ -38,6 +38,11 @@ fn cli_help_flag() {
     assert!(stdout.contains("migrate"));
     assert!(stdout.contains("backfill"));
     assert!(stdout.contains("query"));
+    assert!(stdout.contains("replay"));
+    assert!(stdout.contains("show"));
+    assert!(stdout.contains("git-hook"));
+    assert!(stdout.contains("checkpoint"));
+    assert!(stdout.contains("redact"));
 }
 
 #[test]